@@ -61,29 +61,153 @@ fn HomePage() -> impl IntoView {
     }
 }
 
-/// -------- Bevy Event --------
-#[derive(Event)]
-pub struct TextEvent {
-    pub text: String,
+/// -------- Bevy Events --------
+/// Timeline commands sent from the page to the Bevy app.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum PlaybackCommand {
+    Play,
+    Pause,
+    /// Jump to a time code, clamped to the stage range.
+    Seek(f64),
+}
+
+/// Timeline position reported back from the Bevy app.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct PlaybackState {
+    pub time_code: f64,
+    pub start_time_code: f64,
+    pub end_time_code: f64,
+    pub playing: bool,
+}
+
+/// Camera picked on the page, `0` is the orbit camera.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct SelectCamera(pub usize);
+
+/// Cameras the Bevy app can look through and the one in use.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct CameraList {
+    pub names: Vec<String>,
+    pub active: usize,
 }
 
 #[cfg(target_arch = "wasm32")]
 #[component]
 pub fn CanvasPage() -> impl IntoView {
-    let (text_event_sender, bevy_text_receiver) = event_l2b::<TextEvent>();
-
-    let on_input = move |evt| {
-        text_event_sender
-            .send(TextEvent {
-                text: event_target_value(&evt),
-            })
-            .ok();
-    };
+    let (playback_sender, bevy_playback_receiver) = event_l2b::<PlaybackCommand>();
+    let (playback_receiver, bevy_playback_sender) = event_b2l::<PlaybackState>();
+    let (camera_sender, bevy_camera_receiver) = event_l2b::<SelectCamera>();
+    let (camera_receiver, bevy_camera_sender) = event_b2l::<CameraList>();
+
+    let on_command = Callback::new(move |command: PlaybackCommand| {
+        playback_sender.send(command).ok();
+    });
+    let state = Signal::derive(move || playback_receiver.get());
+    let on_select = Callback::new(move |select: SelectCamera| {
+        camera_sender.send(select).ok();
+    });
+    let cameras = Signal::derive(move || camera_receiver.get());
 
     view! {
         <h2>"Bevy Canvas Integration"</h2>
-        <input type="text" on:input=on_input />
-        <BevyCanvas init=move || crate::usd_viewer::usd_viewer() />
+        <BevyCanvas init=move || {
+            crate::usd_viewer::usd_viewer(
+                bevy_playback_receiver,
+                bevy_playback_sender,
+                bevy_camera_receiver,
+                bevy_camera_sender,
+            )
+        } />
+        <Timeline state on_command />
+        <CameraPicker cameras on_select />
+    }
+}
+
+/// -------- Timeline --------
+#[cfg(target_arch = "wasm32")]
+#[component]
+fn Timeline(
+    /// Latest state reported by Bevy, `None` until the first frame.
+    state: Signal<Option<PlaybackState>>,
+    on_command: Callback<PlaybackCommand>,
+) -> impl IntoView {
+    let time_code = move || state.get().map_or(0.0, |s| s.time_code);
+    let start = move || state.get().map_or(0.0, |s| s.start_time_code);
+    let end = move || state.get().map_or(0.0, |s| s.end_time_code);
+    let playing = move || state.get().is_some_and(|s| s.playing);
+
+    let toggle = move |_| {
+        on_command.run(if playing() {
+            PlaybackCommand::Pause
+        } else {
+            PlaybackCommand::Play
+        })
+    };
+    let step = move |frames: f64| {
+        on_command.run(PlaybackCommand::Pause);
+        on_command.run(PlaybackCommand::Seek(time_code().round() + frames));
+    };
+    let on_scrub = move |evt| {
+        if let Ok(value) = event_target_value(&evt).parse::<f64>() {
+            on_command.run(PlaybackCommand::Seek(value));
+        }
+    };
+
+    view! {
+        <div class="timeline">
+            <button on:click=move |_| step(-1.0)>"<"</button>
+            <button on:click=toggle>{move || if playing() { "Pause" } else { "Play" }}</button>
+            <button on:click=move |_| step(1.0)>">"</button>
+            <input
+                type="range"
+                step="1"
+                prop:min=start
+                prop:max=end
+                prop:value=time_code
+                on:input=on_scrub
+            />
+            <span class="frame">
+                {move || format!("{:.0} / {:.0}", time_code(), end())}
+            </span>
+        </div>
+    }
+}
+
+/// -------- Camera picker --------
+#[cfg(target_arch = "wasm32")]
+#[component]
+fn CameraPicker(
+    /// Cameras reported by Bevy, `None` until the stage is loaded.
+    cameras: Signal<Option<CameraList>>,
+    on_select: Callback<SelectCamera>,
+) -> impl IntoView {
+    let names = move || cameras.get().map(|c| c.names).unwrap_or_default();
+    let active = move || cameras.get().map_or(0, |c| c.active);
+
+    let on_change = move |evt| {
+        if let Ok(index) = event_target_value(&evt).parse::<usize>() {
+            on_select.run(SelectCamera(index));
+        }
+    };
+
+    // the orbit camera alone leaves nothing to pick
+    view! {
+        <Show when=move || { names().len() > 1 }>
+            <div class="cameras">
+                <label>"Camera "</label>
+                <select on:change=on_change prop:value=move || active().to_string()>
+                    {move || {
+                        names()
+                            .into_iter()
+                            .enumerate()
+                            .map(|(index, name)| {
+                                view! { <option value=index.to_string()>{name}</option> }
+                            })
+                            .collect_view()
+                    }}
+                </select>
+            </div>
+        </Show>
     }
 }
 
@@ -0,0 +1,788 @@
+//! Evaluation of `BasisCurves` into tube or ribbon [`MeshData`], and
+//! per-point sizes and colors of `Points`, merged into one mesh for large clouds.
+
+use std::{collections::HashMap, f32::consts::TAU};
+
+use crate::open_rs_loader::{
+    CurveBasis, CurveWrap, CurvesData, MeshData, Orientation, PointsData, Primvar, PrimvarData,
+    PrimvarInterpolation, SubdivisionData,
+};
+
+/// USD's fallback width when none is authored.
+const DEFAULT_WIDTH: f32 = 1.0;
+
+/// Primvars carried from curves onto their generated mesh.
+const DISPLAY_PRIMVARS: [&str; 2] = ["displayColor", "displayOpacity"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurveTessellation {
+    /// Points evaluated along each cubic segment.
+    pub steps_per_segment: usize,
+    /// Sides around each tube.
+    pub sides: usize,
+}
+
+impl Default for CurveTessellation {
+    fn default() -> Self {
+        Self {
+            steps_per_segment: 8,
+            sides: 6,
+        }
+    }
+}
+
+/// A curve evaluated into straight pieces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polyline {
+    pub points: Vec<[f32; 3]>,
+    /// How far along the curve each point is, from 0 to 1.
+    pub params: Vec<f32>,
+    /// The last point joins back to the first.
+    pub closed: bool,
+}
+
+/// Control points of each segment, as indices into the points passed to
+/// [`evaluate_curve`], with pinned curves extended by one phantom point at
+/// each end.
+fn segments(basis: CurveBasis, wrap: CurveWrap, count: usize) -> Vec<[usize; 4]> {
+    let periodic = wrap == CurveWrap::Periodic;
+    match basis {
+        CurveBasis::Linear => Vec::new(),
+        CurveBasis::Bezier if periodic && count >= 3 => (0..count / 3)
+            .map(|k| [3 * k, 3 * k + 1, 3 * k + 2, (3 * k + 3) % count])
+            .collect(),
+        CurveBasis::Bezier if count >= 4 => (0..(count - 1) / 3)
+            .map(|k| [3 * k, 3 * k + 1, 3 * k + 2, 3 * k + 3])
+            .collect(),
+        CurveBasis::Bspline | CurveBasis::CatmullRom if periodic && count >= 3 => (0..count)
+            .map(|k| [k, (k + 1) % count, (k + 2) % count, (k + 3) % count])
+            .collect(),
+        CurveBasis::Bspline | CurveBasis::CatmullRom => {
+            let count = if wrap == CurveWrap::Pinned {
+                count + 2
+            } else {
+                count
+            };
+            (0..count.saturating_sub(3))
+                .map(|k| [k, k + 1, k + 2, k + 3])
+                .collect()
+        }
+        CurveBasis::Bezier => Vec::new(),
+    }
+}
+
+/// Number of pieces a curve of `count` control points is drawn with.
+fn segment_count(basis: CurveBasis, wrap: CurveWrap, count: usize) -> usize {
+    match basis {
+        CurveBasis::Linear if wrap == CurveWrap::Periodic => count,
+        CurveBasis::Linear => count.saturating_sub(1),
+        _ => segments(basis, wrap, count).len(),
+    }
+}
+
+/// Weights of the four control points of a cubic segment at `t`.
+fn cubic_weights(basis: CurveBasis, t: f32) -> [f32; 4] {
+    let (t2, t3) = (t * t, t * t * t);
+    let s = 1.0 - t;
+    match basis {
+        CurveBasis::Bspline => [
+            s * s * s / 6.0,
+            (3.0 * t3 - 6.0 * t2 + 4.0) / 6.0,
+            (-3.0 * t3 + 3.0 * t2 + 3.0 * t + 1.0) / 6.0,
+            t3 / 6.0,
+        ],
+        CurveBasis::CatmullRom => [
+            0.5 * (-t3 + 2.0 * t2 - t),
+            0.5 * (3.0 * t3 - 5.0 * t2 + 2.0),
+            0.5 * (-3.0 * t3 + 4.0 * t2 + t),
+            0.5 * (t3 - t2),
+        ],
+        CurveBasis::Bezier | CurveBasis::Linear => [s * s * s, 3.0 * s * s * t, 3.0 * s * t2, t3],
+    }
+}
+
+/// Evaluate one curve, `None` when it has too few control points.
+pub fn evaluate_curve(
+    points: &[[f32; 3]],
+    basis: CurveBasis,
+    wrap: CurveWrap,
+    steps_per_segment: usize,
+) -> Option<Polyline> {
+    let closed = wrap == CurveWrap::Periodic;
+    if basis == CurveBasis::Linear {
+        if points.len() < 2 {
+            return None;
+        }
+        let pieces = segment_count(basis, wrap, points.len()) as f32;
+        return Some(Polyline {
+            points: points.to_vec(),
+            params: (0..points.len()).map(|i| i as f32 / pieces).collect(),
+            closed,
+        });
+    }
+
+    let segments = segments(basis, wrap, points.len());
+    if segments.is_empty() {
+        return None;
+    }
+    // pinned curves reach their end points through mirrored phantom points
+    let mut controls = points.to_vec();
+    if wrap == CurveWrap::Pinned && basis != CurveBasis::Bezier {
+        let mirror = |a: [f32; 3], b: [f32; 3]| [0, 1, 2].map(|k| 2.0 * a[k] - b[k]);
+        let (first, last) = (points.len() - 1, points.len() - 2);
+        controls.insert(0, mirror(points[0], points[1]));
+        controls.push(mirror(points[first], points[last]));
+    }
+
+    let steps = steps_per_segment.max(1);
+    let mut polyline = Polyline {
+        points: Vec::new(),
+        params: Vec::new(),
+        closed,
+    };
+    for (index, segment) in segments.iter().enumerate() {
+        // open curves also take the very end of their last segment
+        let last = !closed && index + 1 == segments.len();
+        for step in 0..steps + usize::from(last) {
+            let t = step as f32 / steps as f32;
+            let weights = cubic_weights(basis, t);
+            let point = [0, 1, 2].map(|k| {
+                (0..4)
+                    .map(|i| weights[i] * controls[segment[i]][k])
+                    .sum::<f32>()
+            });
+            polyline.points.push(point);
+            polyline
+                .params
+                .push((index as f32 + t) / segments.len() as f32);
+        }
+    }
+    Some(polyline)
+}
+
+/// Values of a primvar indexed into their flat list.
+fn resolved<const N: usize>(values: Vec<[f32; N]>, indices: &Option<Vec<usize>>) -> Vec<[f32; N]> {
+    match indices {
+        Some(indices) => indices
+            .iter()
+            .filter_map(|&i| values.get(i).copied())
+            .collect(),
+        None => values,
+    }
+}
+
+/// A primvar flattened to `N` floats per element.
+struct Samples<const N: usize> {
+    values: Vec<[f32; N]>,
+    interpolation: PrimvarInterpolation,
+}
+
+impl Samples<1> {
+    fn from_float(primvar: &Primvar<f32>) -> Self {
+        Self {
+            values: resolved(
+                primvar.values.iter().map(|&v| [v]).collect(),
+                &primvar.indices,
+            ),
+            interpolation: primvar.interpolation,
+        }
+    }
+}
+
+impl Samples<3> {
+    fn from_float3(primvar: &Primvar<[f32; 3]>) -> Self {
+        Self {
+            values: resolved(primvar.values.clone(), &primvar.indices),
+            interpolation: primvar.interpolation,
+        }
+    }
+}
+
+impl<const N: usize> Samples<N> {
+    /// Values belonging to one curve or point; `first` and `count` locate it
+    /// among vertex and varying values, `element` among uniform ones.
+    fn span(&self, element: usize, first: usize, count: usize) -> Option<&[[f32; N]]> {
+        match self.interpolation {
+            PrimvarInterpolation::Constant => self.values.get(..1),
+            PrimvarInterpolation::Uniform => self.values.get(element..element + 1),
+            _ => self.values.get(first..first + count),
+        }
+    }
+}
+
+/// Linear blend of `values` spread evenly from 0 to 1, or around a loop.
+fn sample<const N: usize>(values: &[[f32; N]], param: f32, closed: bool) -> [f32; N] {
+    let len = values.len();
+    if len == 1 {
+        return values[0];
+    }
+    let x = if closed {
+        param * len as f32
+    } else {
+        param * (len - 1) as f32
+    };
+    let i = (x.floor() as usize).min(len - 1);
+    let j = if closed {
+        (i + 1) % len
+    } else {
+        (i + 1).min(len - 1)
+    };
+    let f = x - i as f32;
+    std::array::from_fn(|k| values[i][k] * (1.0 - f) + values[j][k] * f)
+}
+
+/// Where each curve's vertex and varying values start.
+struct CurveLayout {
+    vertex_first: Vec<usize>,
+    varying_first: Vec<usize>,
+    varying_counts: Vec<usize>,
+}
+
+impl CurveLayout {
+    fn new(curves: &CurvesData) -> Self {
+        let mut layout = CurveLayout {
+            vertex_first: Vec::new(),
+            varying_first: Vec::new(),
+            varying_counts: Vec::new(),
+        };
+        let (mut vertex, mut varying) = (0, 0);
+        for &count in &curves.curve_vertex_counts {
+            // varying values sit on segment ends, shared around a loop
+            let pieces = segment_count(curves.basis, curves.wrap, count);
+            let varying_count = match curves.wrap {
+                CurveWrap::Periodic => pieces,
+                _ => pieces + 1,
+            };
+            layout.vertex_first.push(vertex);
+            layout.varying_first.push(varying);
+            layout.varying_counts.push(varying_count);
+            vertex += count;
+            varying += varying_count;
+        }
+        layout
+    }
+
+    fn span<'a, const N: usize>(
+        &self,
+        samples: &'a Samples<N>,
+        curves: &CurvesData,
+        curve: usize,
+    ) -> Option<&'a [[f32; N]]> {
+        let span = match samples.interpolation {
+            PrimvarInterpolation::Vertex => samples.span(
+                curve,
+                self.vertex_first[curve],
+                curves.curve_vertex_counts[curve],
+            ),
+            _ => samples.span(curve, self.varying_first[curve], self.varying_counts[curve]),
+        };
+        span.filter(|values| !values.is_empty())
+    }
+}
+
+fn normalize(v: [f32; 3]) -> Option<[f32; 3]> {
+    let length = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    (length > 1e-12).then(|| v.map(|c| c / length))
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Direction of the polyline through each point.
+fn tangents(polyline: &Polyline) -> Vec<[f32; 3]> {
+    let points = &polyline.points;
+    let n = points.len();
+    (0..n)
+        .map(|i| {
+            let (prev, next) = if polyline.closed {
+                ((i + n - 1) % n, (i + 1) % n)
+            } else {
+                (i.saturating_sub(1), (i + 1).min(n - 1))
+            };
+            let d = [0, 1, 2].map(|k| points[next][k] - points[prev][k]);
+            normalize(d).unwrap_or([0.0, 0.0, 1.0])
+        })
+        .collect()
+}
+
+/// A normal per point that turns as little as possible along the curve.
+fn transported_normals(tangents: &[[f32; 3]]) -> Vec<[f32; 3]> {
+    let first = tangents[0];
+    let helper = if first[0].abs() < 0.9 {
+        [1.0, 0.0, 0.0]
+    } else {
+        [0.0, 1.0, 0.0]
+    };
+    let mut normal = normalize(cross(first, helper)).unwrap_or([0.0, 1.0, 0.0]);
+    tangents
+        .iter()
+        .map(|&t| {
+            let along = dot(normal, t);
+            normal = normalize([0, 1, 2].map(|k| normal[k] - along * t[k])).unwrap_or(normal);
+            normal
+        })
+        .collect()
+}
+
+/// Generated geometry, one display value per point.
+#[derive(Default)]
+struct CurveMesh {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    colors: Vec<[f32; 3]>,
+    opacities: Vec<f32>,
+    face_vertex_counts: Vec<usize>,
+    face_vertex_indices: Vec<usize>,
+}
+
+/// Constant display primvars are kept as they are, others are sampled
+/// along each curve.
+fn varying_display_samples(curves: &CurvesData) -> (Option<Samples<3>>, Option<Samples<1>>) {
+    let color = curves
+        .primvars
+        .get("displayColor")
+        .and_then(PrimvarData::as_float3)
+        .filter(|p| p.interpolation != PrimvarInterpolation::Constant)
+        .map(Samples::from_float3);
+    let opacity = curves
+        .primvars
+        .get("displayOpacity")
+        .and_then(PrimvarData::as_float)
+        .filter(|p| p.interpolation != PrimvarInterpolation::Constant)
+        .map(Samples::from_float);
+    (color, opacity)
+}
+
+/// Sweep every curve into a tube, or a ribbon facing its authored normals.
+///
+/// Widths, normals and display primvars are blended linearly along each
+/// curve, whatever its basis.
+pub fn curves_to_mesh(curves: &CurvesData, tessellation: &CurveTessellation) -> MeshData {
+    let layout = CurveLayout::new(curves);
+    let widths = curves.widths.as_ref().map(Samples::from_float);
+    let normals = curves.normals.as_ref().map(Samples::from_float3);
+    let (colors, opacities) = varying_display_samples(curves);
+    let sides = tessellation.sides.max(3);
+
+    let mut mesh = CurveMesh::default();
+    let mut first = 0;
+    for (curve, &count) in curves.curve_vertex_counts.iter().enumerate() {
+        let controls = &curves.points[first..first + count];
+        first += count;
+        let Some(polyline) = evaluate_curve(
+            controls,
+            curves.basis,
+            curves.wrap,
+            tessellation.steps_per_segment,
+        ) else {
+            continue;
+        };
+
+        let closed = polyline.closed;
+        let span_sample = |samples: &Option<Samples<1>>, param: f32| {
+            samples
+                .as_ref()
+                .and_then(|s| layout.span(s, curves, curve))
+                .map(|values| sample(values, param, closed)[0])
+        };
+        let span_sample3 = |samples: &Option<Samples<3>>, param: f32| {
+            samples
+                .as_ref()
+                .and_then(|s| layout.span(s, curves, curve))
+                .map(|values| sample(values, param, closed))
+        };
+
+        let facing = normals.as_ref().and_then(|s| layout.span(s, curves, curve));
+        let tangents = tangents(&polyline);
+        let frames = transported_normals(&tangents);
+        let rings: Vec<usize> = polyline
+            .points
+            .iter()
+            .zip(&polyline.params)
+            .enumerate()
+            .map(|(i, (&center, &param))| {
+                let radius = 0.5 * span_sample(&widths, param).unwrap_or(DEFAULT_WIDTH);
+                let color = span_sample3(&colors, param).unwrap_or([1.0; 3]);
+                let opacity = span_sample(&opacities, param).unwrap_or(1.0);
+                let tangent = tangents[i];
+                let start = mesh.positions.len();
+                let mut push = |position: [f32; 3], normal: [f32; 3]| {
+                    mesh.positions.push(position);
+                    mesh.normals.push(normal);
+                    mesh.colors.push(color);
+                    mesh.opacities.push(opacity);
+                };
+
+                match facing {
+                    // ribbon edges sit across the curve, perpendicular to the normal
+                    Some(values) => {
+                        let facing = normalize(sample(values, param, closed)).unwrap_or(frames[i]);
+                        let across = normalize(cross(tangent, facing)).unwrap_or(frames[i]);
+                        for side in [-1.0, 1.0] {
+                            let offset = across.map(|c| side * radius * c);
+                            push([0, 1, 2].map(|k| center[k] + offset[k]), facing);
+                        }
+                    }
+                    None => {
+                        let normal = frames[i];
+                        let binormal = cross(tangent, normal);
+                        for s in 0..sides {
+                            let (sin, cos) = (TAU * s as f32 / sides as f32).sin_cos();
+                            let out = [0, 1, 2].map(|k| cos * normal[k] + sin * binormal[k]);
+                            push([0, 1, 2].map(|k| center[k] + radius * out[k]), out);
+                        }
+                    }
+                }
+                start
+            })
+            .collect();
+
+        let joins = if closed { rings.len() } else { rings.len() - 1 };
+        for i in 0..joins {
+            let (low, high) = (rings[i], rings[(i + 1) % rings.len()]);
+            if facing.is_some() {
+                // across the curve, then along it, turns towards the normal
+                mesh.face_vertex_counts.push(4);
+                mesh.face_vertex_indices
+                    .extend([low, low + 1, high + 1, high]);
+                continue;
+            }
+            for s in 0..sides {
+                let t = (s + 1) % sides;
+                mesh.face_vertex_counts.push(4);
+                mesh.face_vertex_indices
+                    .extend([low + s, low + t, high + t, high + s]);
+            }
+        }
+    }
+
+    let mut primvars = HashMap::from([(
+        "normals".to_string(),
+        PrimvarData::Float3(vertex_primvar(mesh.normals, "normal3f[]")),
+    )]);
+    for name in DISPLAY_PRIMVARS {
+        if let Some(primvar) = curves.primvars.get(name) {
+            if primvar.interpolation() == PrimvarInterpolation::Constant {
+                primvars.insert(name.to_string(), primvar.clone());
+            }
+        }
+    }
+    if colors.is_some() {
+        primvars.insert(
+            "displayColor".to_string(),
+            PrimvarData::Float3(vertex_primvar(mesh.colors, "color3f[]")),
+        );
+    }
+    if opacities.is_some() {
+        primvars.insert(
+            "displayOpacity".to_string(),
+            PrimvarData::Float(vertex_primvar(mesh.opacities, "float[]")),
+        );
+    }
+
+    MeshData {
+        positions: mesh.positions,
+        face_vertex_counts: mesh.face_vertex_counts,
+        face_vertex_indices: mesh.face_vertex_indices,
+        primvars,
+        double_sided: curves.normals.is_some(),
+        material_index: curves.material_index,
+        subdivision: SubdivisionData::default(),
+        hole_indices: Vec::new(),
+        orientation: Orientation::RightHanded,
+    }
+}
+
+fn vertex_primvar<T>(values: Vec<T>, type_name: &str) -> Primvar<T> {
+    Primvar {
+        values,
+        indices: None,
+        interpolation: PrimvarInterpolation::Vertex,
+        element_size: 1,
+        type_name: type_name.to_string(),
+    }
+}
+
+/// Radius of every point, half its width.
+pub fn point_radii(points: &PointsData) -> Vec<f32> {
+    let widths = points.widths.as_ref().map(Samples::from_float);
+    (0..points.positions.len())
+        .map(|index| {
+            let width = widths
+                .as_ref()
+                .and_then(|widths| widths.span(0, index, 1))
+                .map_or(DEFAULT_WIDTH, |values| values[0][0]);
+            0.5 * width
+        })
+        .collect()
+}
+
+/// `displayColor` of every point in linear RGB, `None` when not authored.
+pub fn point_colors(points: &PointsData) -> Option<Vec<[f32; 3]>> {
+    let colors = points
+        .primvars
+        .get("displayColor")
+        .and_then(PrimvarData::as_float3)
+        .map(Samples::from_float3)?;
+    (0..points.positions.len())
+        .map(|index| colors.span(0, index, 1).map(|values| values[0]))
+        .collect()
+}
+
+/// All points of `points` merged into one mesh, a copy of `marker` scaled to
+/// each point's radius. `marker` is a unit shape around the origin; its
+/// normals are kept and `displayColor` becomes a per-vertex primvar.
+pub fn points_to_mesh(points: &PointsData, marker: &MeshData) -> MeshData {
+    let radii = point_radii(points);
+    let colors = point_colors(points);
+    let marker_normals = marker
+        .primvars
+        .get("normals")
+        .and_then(PrimvarData::as_float3)
+        .filter(|normals| normals.values.len() == marker.positions.len());
+
+    let copies = points.positions.len();
+    let vertex_count = marker.positions.len();
+    let mut positions = Vec::with_capacity(copies * vertex_count);
+    let mut normals = Vec::new();
+    let mut vertex_colors = Vec::new();
+    let mut face_vertex_indices = Vec::with_capacity(copies * marker.face_vertex_indices.len());
+    for (point, (center, radius)) in points.positions.iter().zip(&radii).enumerate() {
+        let offset = positions.len();
+        positions.extend(
+            marker
+                .positions
+                .iter()
+                .map(|p| [0, 1, 2].map(|k| center[k] + radius * p[k])),
+        );
+        if let Some(marker_normals) = marker_normals {
+            normals.extend_from_slice(&marker_normals.values);
+        }
+        if let Some(colors) = &colors {
+            vertex_colors.extend(std::iter::repeat_n(colors[point], vertex_count));
+        }
+        face_vertex_indices.extend(marker.face_vertex_indices.iter().map(|&i| offset + i));
+    }
+
+    let mut primvars = HashMap::new();
+    if marker_normals.is_some() {
+        primvars.insert(
+            "normals".to_string(),
+            PrimvarData::Float3(vertex_primvar(normals, "normal3f[]")),
+        );
+    }
+    if colors.is_some() {
+        primvars.insert(
+            "displayColor".to_string(),
+            PrimvarData::Float3(vertex_primvar(vertex_colors, "color3f[]")),
+        );
+    }
+    MeshData {
+        positions,
+        face_vertex_counts: marker.face_vertex_counts.repeat(copies),
+        face_vertex_indices,
+        primvars,
+        double_sided: false,
+        material_index: points.material_index,
+        subdivision: SubdivisionData::default(),
+        hole_indices: Vec::new(),
+        orientation: marker.orientation,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_rs_loader::InstanceTransform;
+
+    fn curves(points: Vec<[f32; 3]>, basis: CurveBasis) -> CurvesData {
+        CurvesData {
+            path: "/curves".to_string(),
+            curve_vertex_counts: vec![points.len()],
+            points,
+            basis,
+            wrap: CurveWrap::Nonperiodic,
+            widths: None,
+            normals: None,
+            primvars: HashMap::new(),
+            material_index: None,
+            transform: InstanceTransform::Static([[0.0; 4]; 4]),
+        }
+    }
+
+    fn assert_close(a: [f32; 3], b: [f32; 3]) {
+        assert!((0..3).all(|k| (a[k] - b[k]).abs() < 1e-5), "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn cubic_bases_interpolate_or_approximate() {
+        let controls = [
+            [0.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [2.0, 1.0, 0.0],
+            [3.0, 0.0, 0.0],
+        ];
+        // bezier passes through its end points
+        let bezier =
+            evaluate_curve(&controls, CurveBasis::Bezier, CurveWrap::Nonperiodic, 4).unwrap();
+        assert_eq!(bezier.points.len(), 5);
+        assert_close(bezier.points[0], controls[0]);
+        assert_close(bezier.points[4], controls[3]);
+        assert_close(bezier.points[2], [1.5, 0.75, 0.0]);
+
+        // catmull-rom passes through its inner points
+        let catmull =
+            evaluate_curve(&controls, CurveBasis::CatmullRom, CurveWrap::Nonperiodic, 2).unwrap();
+        assert_close(catmull.points[0], controls[1]);
+        assert_close(*catmull.points.last().unwrap(), controls[2]);
+
+        // pinned b-splines reach the first and last control point
+        let pinned = evaluate_curve(&controls, CurveBasis::Bspline, CurveWrap::Pinned, 4).unwrap();
+        assert_close(pinned.points[0], controls[0]);
+        assert_close(*pinned.points.last().unwrap(), controls[3]);
+        assert_eq!(pinned.params.last(), Some(&1.0));
+    }
+
+    #[test]
+    fn periodic_curves_close() {
+        let square = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0],
+        ];
+        let loop_ = evaluate_curve(&square, CurveBasis::Bspline, CurveWrap::Periodic, 3).unwrap();
+        assert!(loop_.closed);
+        assert_eq!(loop_.points.len(), 12);
+        let linear = evaluate_curve(&square, CurveBasis::Linear, CurveWrap::Periodic, 3).unwrap();
+        assert_eq!(linear.params, vec![0.0, 0.25, 0.5, 0.75]);
+    }
+
+    #[test]
+    fn tubes_follow_widths() {
+        let mut data = curves(
+            vec![[0.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 0.0, 2.0]],
+            CurveBasis::Linear,
+        );
+        data.widths = Some(vertex_primvar(vec![2.0, 1.0, 0.0], "float[]"));
+        let mesh = curves_to_mesh(&data, &CurveTessellation::default());
+
+        // three rings of six, joined by two bands of quads
+        assert_eq!(mesh.positions.len(), 18);
+        assert_eq!(mesh.face_vertex_counts, vec![4; 12]);
+        let radius = |p: [f32; 3]| (p[0] * p[0] + p[1] * p[1]).sqrt();
+        assert!((radius(mesh.positions[0]) - 1.0).abs() < 1e-5);
+        assert!((radius(mesh.positions[6]) - 0.5).abs() < 1e-5);
+        assert!(radius(mesh.positions[12]) < 1e-5);
+        assert!(!mesh.double_sided);
+    }
+
+    #[test]
+    fn ribbons_face_their_normals() {
+        let mut data = curves(vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0]], CurveBasis::Linear);
+        let mut normals = vertex_primvar(vec![[0.0, 0.0, 1.0]], "normal3f[]");
+        normals.interpolation = PrimvarInterpolation::Constant;
+        data.normals = Some(normals);
+        data.primvars.insert(
+            "displayColor".to_string(),
+            PrimvarData::Float3(vertex_primvar(
+                vec![[1.0, 0.0, 0.0], [0.0, 0.0, 1.0]],
+                "color3f[]",
+            )),
+        );
+        let mesh = curves_to_mesh(&data, &CurveTessellation::default());
+
+        assert_eq!(mesh.face_vertex_counts, vec![4]);
+        let corners: Vec<[f32; 3]> = mesh
+            .face_vertex_indices
+            .iter()
+            .map(|&i| mesh.positions[i])
+            .collect();
+        let facing = cross(
+            [0, 1, 2].map(|k| corners[1][k] - corners[0][k]),
+            [0, 1, 2].map(|k| corners[2][k] - corners[1][k]),
+        );
+        assert!(facing[2] > 0.0, "{facing:?}");
+        assert!(mesh.double_sided);
+
+        let colors = mesh.primvars["displayColor"].as_float3().unwrap();
+        assert_eq!(colors.interpolation, PrimvarInterpolation::Vertex);
+        assert_eq!(
+            colors.values,
+            vec![
+                [1.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0],
+                [0.0, 0.0, 1.0]
+            ]
+        );
+    }
+
+    #[test]
+    fn points_read_widths_and_colors() {
+        let mut widths = vertex_primvar(vec![0.5], "float[]");
+        widths.interpolation = PrimvarInterpolation::Constant;
+        let points = PointsData {
+            path: "/points".to_string(),
+            positions: vec![[0.0; 3]; 2],
+            widths: Some(widths),
+            primvars: HashMap::from([(
+                "displayColor".to_string(),
+                PrimvarData::Float3(vertex_primvar(
+                    vec![[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+                    "color3f[]",
+                )),
+            )]),
+            material_index: None,
+            transform: InstanceTransform::Static([[0.0; 4]; 4]),
+        };
+        assert_eq!(point_radii(&points), vec![0.25, 0.25]);
+        assert_eq!(
+            point_colors(&points),
+            Some(vec![[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]])
+        );
+    }
+
+    #[test]
+    fn large_point_clouds_merge_into_one_mesh() {
+        let points = PointsData {
+            path: "/points".to_string(),
+            positions: vec![[0.0; 3], [10.0, 0.0, 0.0]],
+            widths: Some(vertex_primvar(vec![2.0, 4.0], "float[]")),
+            primvars: HashMap::from([(
+                "displayColor".to_string(),
+                PrimvarData::Float3(vertex_primvar(
+                    vec![[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+                    "color3f[]",
+                )),
+            )]),
+            material_index: Some(3),
+            transform: InstanceTransform::Static([[0.0; 4]; 4]),
+        };
+        let marker = crate::gprims::cube(2.0);
+        let mesh = points_to_mesh(&points, &marker);
+
+        assert_eq!(mesh.positions.len(), 48);
+        assert_eq!(mesh.face_vertex_counts, vec![4; 12]);
+        assert_eq!(
+            mesh.face_vertex_indices[24],
+            24 + marker.face_vertex_indices[0]
+        );
+        // the second cube is twice as large and moved over
+        assert_eq!(mesh.positions[24], [10.0 + 2.0, -2.0, -2.0]);
+        assert_eq!(mesh.primvars["normals"].element_count(), 48);
+        let colors = mesh.primvars["displayColor"].as_float3().unwrap();
+        assert_eq!(colors.values[23], [1.0, 0.0, 0.0]);
+        assert_eq!(colors.values[24], [0.0, 1.0, 0.0]);
+        assert_eq!(mesh.material_index, Some(3));
+    }
+}
@@ -0,0 +1,399 @@
+//! Tessellation of `UsdGeom` implicit surfaces into [`MeshData`].
+//!
+//! Shapes follow the schema conventions: centred on the origin, built
+//! around their `axis`, with counter-clockwise faces seen from outside.
+
+use std::{
+    collections::HashMap,
+    f32::consts::{FRAC_PI_2, TAU},
+};
+
+use crate::open_rs_loader::{
+    MeshData, Orientation, Primvar, PrimvarData, PrimvarInterpolation, SubdivisionData,
+};
+
+/// Spine of a cylinder, cone or capsule, or the normal of a plane.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    #[default]
+    Z,
+}
+
+impl Axis {
+    pub fn from_token(token: &str) -> Option<Self> {
+        match token {
+            "X" => Some(Axis::X),
+            "Y" => Some(Axis::Y),
+            "Z" => Some(Axis::Z),
+            _ => None,
+        }
+    }
+
+    /// Move a point built around +Z onto this axis; the cyclic swap keeps
+    /// the winding intact.
+    fn orient(self, [x, y, z]: [f32; 3]) -> [f32; 3] {
+        match self {
+            Axis::X => [z, x, y],
+            Axis::Y => [y, z, x],
+            Axis::Z => [x, y, z],
+        }
+    }
+}
+
+/// Fewest segments around a full circle.
+const MIN_SEGMENTS: u32 = 3;
+
+/// Points with one normal each, gathered into faces.
+#[derive(Default)]
+struct Builder {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    face_vertex_counts: Vec<usize>,
+    face_vertex_indices: Vec<usize>,
+}
+
+impl Builder {
+    fn point(&mut self, position: [f32; 3], normal: [f32; 3]) -> usize {
+        self.positions.push(position);
+        self.normals.push(normal);
+        self.positions.len() - 1
+    }
+
+    fn face(&mut self, corners: &[usize]) {
+        self.face_vertex_counts.push(corners.len());
+        self.face_vertex_indices.extend_from_slice(corners);
+    }
+
+    fn finish(self) -> MeshData {
+        let normals = PrimvarData::Float3(Primvar {
+            values: self.normals,
+            indices: None,
+            interpolation: PrimvarInterpolation::Vertex,
+            element_size: 1,
+            type_name: "normal3f[]".to_string(),
+        });
+        MeshData {
+            positions: self.positions,
+            face_vertex_counts: self.face_vertex_counts,
+            face_vertex_indices: self.face_vertex_indices,
+            primvars: HashMap::from([("normals".to_string(), normals)]),
+            double_sided: false,
+            material_index: None,
+            subdivision: SubdivisionData::default(),
+            hole_indices: Vec::new(),
+            orientation: Orientation::RightHanded,
+        }
+    }
+}
+
+/// A point on the outline of a surface of revolution: distance from the
+/// axis, height along it and the outward normal in that plane.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ProfilePoint {
+    r: f32,
+    z: f32,
+    nr: f32,
+    nz: f32,
+}
+
+impl ProfilePoint {
+    fn new(r: f32, z: f32, nr: f32, nz: f32) -> Self {
+        Self { r, z, nr, nz }
+    }
+}
+
+/// Sweep `profile`, listed bottom to top, around the axis.
+///
+/// Consecutive points at the same spot start a hard edge and are not joined.
+fn revolve(profile: &[ProfilePoint], segments: u32, axis: Axis) -> MeshData {
+    let segments = segments.max(MIN_SEGMENTS) as usize;
+    let mut builder = Builder::default();
+    let rings: Vec<usize> = profile
+        .iter()
+        .map(|p| {
+            let first = builder.positions.len();
+            for s in 0..segments {
+                let (sin, cos) = (TAU * s as f32 / segments as f32).sin_cos();
+                builder.point(
+                    axis.orient([p.r * cos, p.r * sin, p.z]),
+                    axis.orient([p.nr * cos, p.nr * sin, p.nz]),
+                );
+            }
+            first
+        })
+        .collect();
+
+    for (i, pair) in profile.windows(2).enumerate() {
+        let (low, high) = (pair[0], pair[1]);
+        if (low.r, low.z) == (high.r, high.z) || (low.r == 0.0 && high.r == 0.0) {
+            continue;
+        }
+        for s in 0..segments {
+            let t = (s + 1) % segments;
+            let (a, b) = (rings[i] + s, rings[i] + t);
+            let (c, d) = (rings[i + 1] + t, rings[i + 1] + s);
+            // a ring on the axis collapses the quad into a triangle
+            if low.r == 0.0 {
+                builder.face(&[a, c, d]);
+            } else if high.r == 0.0 {
+                builder.face(&[a, b, c]);
+            } else {
+                builder.face(&[a, b, c, d]);
+            }
+        }
+    }
+    builder.finish()
+}
+
+/// Points along a quarter circle of `radius` centred at height `z`, from
+/// latitude `from` to `to` in `steps` steps.
+fn arc(radius: f32, z: f32, from: f32, to: f32, steps: usize) -> Vec<ProfilePoint> {
+    (0..=steps)
+        .map(|i| {
+            let latitude = from + (to - from) * i as f32 / steps as f32;
+            let (sin, cos) = latitude.sin_cos();
+            // pin the poles onto the axis so they close cleanly
+            let cos = if latitude.abs() == FRAC_PI_2 {
+                0.0
+            } else {
+                cos
+            };
+            ProfilePoint::new(radius * cos, z + radius * sin, cos, sin)
+        })
+        .collect()
+}
+
+/// Rings from pole to pole for `segments` around the equator.
+fn sphere_rings(segments: u32) -> usize {
+    (segments.max(MIN_SEGMENTS) as usize / 2).max(2)
+}
+
+/// For each face of [`cube`], the face of USD's own cube topology, ordered
+/// +Z, -Z, +Y, -Y, +X, -X, that uniform primvars on a Cube are authored for.
+pub const CUBE_USD_FACES: [usize; 6] = [4, 5, 2, 3, 0, 1];
+
+/// An axis-aligned cube with edges of length `size`.
+pub fn cube(size: f32) -> MeshData {
+    let h = 0.5 * size;
+    // normal, then two edge directions whose cross product is the normal
+    let sides: [([f32; 3], [f32; 3], [f32; 3]); 6] = [
+        ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]),
+        ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+        ([0.0, 1.0, 0.0], [0.0, 0.0, 1.0], [1.0, 0.0, 0.0]),
+        ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+        ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+        ([0.0, 0.0, -1.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]),
+    ];
+
+    let mut builder = Builder::default();
+    for (n, u, v) in sides {
+        let corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].map(|(su, sv)| {
+            let position = [0, 1, 2].map(|k| h * (n[k] + su * u[k] + sv * v[k]));
+            builder.point(position, n)
+        });
+        builder.face(&corners);
+    }
+    builder.finish()
+}
+
+pub fn sphere(radius: f32, segments: u32) -> MeshData {
+    let profile = arc(radius, 0.0, -FRAC_PI_2, FRAC_PI_2, sphere_rings(segments));
+    revolve(&profile, segments, Axis::Z)
+}
+
+/// A capped cylinder `height` long.
+pub fn cylinder(radius: f32, height: f32, axis: Axis, segments: u32) -> MeshData {
+    let h = 0.5 * height;
+    let profile = [
+        ProfilePoint::new(0.0, -h, 0.0, -1.0),
+        ProfilePoint::new(radius, -h, 0.0, -1.0),
+        ProfilePoint::new(radius, -h, 1.0, 0.0),
+        ProfilePoint::new(radius, h, 1.0, 0.0),
+        ProfilePoint::new(radius, h, 0.0, 1.0),
+        ProfilePoint::new(0.0, h, 0.0, 1.0),
+    ];
+    revolve(&profile, segments, axis)
+}
+
+/// A capped cone `height` long with its apex towards the positive axis.
+pub fn cone(radius: f32, height: f32, axis: Axis, segments: u32) -> MeshData {
+    let h = 0.5 * height;
+    let slant = radius.hypot(height);
+    let (nr, nz) = if slant > 0.0 {
+        (height / slant, radius / slant)
+    } else {
+        (1.0, 0.0)
+    };
+    let profile = [
+        ProfilePoint::new(0.0, -h, 0.0, -1.0),
+        ProfilePoint::new(radius, -h, 0.0, -1.0),
+        ProfilePoint::new(radius, -h, nr, nz),
+        ProfilePoint::new(0.0, h, nr, nz),
+    ];
+    revolve(&profile, segments, axis)
+}
+
+/// A cylinder `height` long between two hemispherical caps.
+pub fn capsule(radius: f32, height: f32, axis: Axis, segments: u32) -> MeshData {
+    let h = 0.5 * height;
+    let steps = (sphere_rings(segments) / 2).max(1);
+    let mut profile = arc(radius, -h, -FRAC_PI_2, 0.0, steps);
+    profile.extend(arc(radius, h, 0.0, FRAC_PI_2, steps));
+    revolve(&profile, segments, axis)
+}
+
+/// A single quad facing the positive `axis`.
+///
+/// `width` runs along X, or Z for an X axis; `length` along Y, or Z for a
+/// Y axis.
+pub fn plane(width: f32, length: f32, axis: Axis) -> MeshData {
+    let (w, l) = (0.5 * width, 0.5 * length);
+    let (normal, u, v) = match axis {
+        Axis::X => ([1.0, 0.0, 0.0], [0.0, 0.0, w], [0.0, l, 0.0]),
+        Axis::Y => ([0.0, 1.0, 0.0], [w, 0.0, 0.0], [0.0, 0.0, l]),
+        Axis::Z => ([0.0, 0.0, 1.0], [w, 0.0, 0.0], [0.0, l, 0.0]),
+    };
+
+    let mut builder = Builder::default();
+    let mut corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+        .map(|(su, sv)| builder.point([0, 1, 2].map(|k| su * u[k] + sv * v[k]), normal));
+    // keep the corners counter-clockwise around the normal
+    let cross = [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ];
+    if (0..3).map(|k| cross[k] * normal[k]).sum::<f32>() < 0.0 {
+        corners.reverse();
+    }
+    builder.face(&corners);
+    builder.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Newell normal and centroid of every face.
+    fn faces(mesh: &MeshData) -> Vec<([f32; 3], [f32; 3])> {
+        let mut start = 0;
+        mesh.face_vertex_counts
+            .iter()
+            .map(|&count| {
+                let corners: Vec<[f32; 3]> = mesh.face_vertex_indices[start..start + count]
+                    .iter()
+                    .map(|&i| mesh.positions[i])
+                    .collect();
+                start += count;
+                let mut normal = [0.0; 3];
+                let mut centroid = [0.0; 3];
+                for (i, a) in corners.iter().enumerate() {
+                    let b = corners[(i + 1) % count];
+                    normal[0] += (a[1] - b[1]) * (a[2] + b[2]);
+                    normal[1] += (a[2] - b[2]) * (a[0] + b[0]);
+                    normal[2] += (a[0] - b[0]) * (a[1] + b[1]);
+                    for k in 0..3 {
+                        centroid[k] += a[k] / count as f32;
+                    }
+                }
+                (normal, centroid)
+            })
+            .collect()
+    }
+
+    fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+        a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+    }
+
+    /// Every face of a convex shape around the origin faces away from it.
+    fn assert_outward(mesh: &MeshData) {
+        for (normal, centroid) in faces(mesh) {
+            assert!(dot(normal, centroid) > 0.0, "{normal:?} at {centroid:?}");
+        }
+    }
+
+    fn bounds(mesh: &MeshData) -> ([f32; 3], [f32; 3]) {
+        mesh.positions
+            .iter()
+            .fold(([f32::MAX; 3], [f32::MIN; 3]), |(lo, hi), p| {
+                (
+                    [0, 1, 2].map(|k| lo[k].min(p[k])),
+                    [0, 1, 2].map(|k| hi[k].max(p[k])),
+                )
+            })
+    }
+
+    #[test]
+    fn cube_has_flat_outward_faces() {
+        let mesh = cube(2.0);
+        assert_eq!(mesh.face_vertex_counts, vec![4; 6]);
+        assert_eq!(bounds(&mesh), ([-1.0; 3], [1.0; 3]));
+        assert_outward(&mesh);
+
+        let normals = mesh.primvars["normals"].as_float3().unwrap();
+        for ((face_normal, _), corners) in
+            faces(&mesh).iter().zip(mesh.face_vertex_indices.chunks(4))
+        {
+            for &corner in corners {
+                assert!(dot(normals.values[corner], *face_normal) > 0.0);
+            }
+        }
+
+        let usd_normals = [
+            [0.0, 0.0, 1.0],
+            [0.0, 0.0, -1.0],
+            [0.0, 1.0, 0.0],
+            [0.0, -1.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [-1.0, 0.0, 0.0],
+        ];
+        for ((normal, _), usd_face) in faces(&mesh).iter().zip(CUBE_USD_FACES) {
+            assert!(dot(*normal, usd_normals[usd_face]) > 0.0, "{normal:?}");
+        }
+    }
+
+    #[test]
+    fn sphere_closes_at_the_poles() {
+        let mesh = sphere(2.0, 8);
+        for p in &mesh.positions {
+            assert!((dot(*p, *p).sqrt() - 2.0).abs() < 1e-5);
+        }
+        // 4 rings: triangle fans at both poles and quads in between
+        let triangles = mesh.face_vertex_counts.iter().filter(|&&c| c == 3).count();
+        assert_eq!(triangles, 16);
+        assert_eq!(mesh.face_vertex_counts.len(), 32);
+        assert_outward(&mesh);
+    }
+
+    #[test]
+    fn revolved_shapes_follow_their_axis() {
+        let cylinder = cylinder(1.0, 4.0, Axis::X, 12);
+        let (lo, hi) = bounds(&cylinder);
+        assert!((lo[0] + 2.0).abs() < 1e-6 && (hi[0] - 2.0).abs() < 1e-6);
+        assert!((hi[1] - 1.0).abs() < 1e-6 && (hi[2] - 1.0).abs() < 1e-6);
+        assert_outward(&cylinder);
+
+        let cone = cone(1.0, 2.0, Axis::Y, 12);
+        let apex = cone.positions.iter().map(|p| p[1]).fold(f32::MIN, f32::max);
+        assert_eq!(apex, 1.0);
+        assert_outward(&cone);
+
+        let capsule = capsule(0.5, 1.0, Axis::Z, 12);
+        let (lo, hi) = bounds(&capsule);
+        assert!((lo[2] + 1.0).abs() < 1e-6 && (hi[2] - 1.0).abs() < 1e-6);
+        assert_outward(&capsule);
+    }
+
+    #[test]
+    fn plane_faces_its_axis() {
+        for axis in [Axis::X, Axis::Y, Axis::Z] {
+            let mesh = plane(2.0, 4.0, axis);
+            let (normal, _) = faces(&mesh)[0];
+            assert!(dot(normal, axis.orient([0.0, 0.0, 1.0])) > 0.0, "{axis:?}");
+        }
+        let (lo, hi) = bounds(&plane(2.0, 4.0, Axis::Y));
+        assert_eq!((lo, hi), ([-1.0, 0.0, -2.0], [1.0, 0.0, 2.0]));
+    }
+}
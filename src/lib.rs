@@ -1,5 +1,8 @@
 pub mod app;
+pub mod curves;
+pub mod gprims;
 pub mod open_rs_loader;
+pub mod subdivision;
 pub mod usd_viewer;
 pub mod usdish;
 
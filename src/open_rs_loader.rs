@@ -1,12 +1,81 @@
-use std::collections::HashMap;
+use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    fmt,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::Path,
+    rc::Rc,
+};
 
-use glam::{Mat4, Quat, Vec3};
+use crate::gprims::{self, Axis};
+use glam::{DMat4, DQuat, DVec3, Mat4, Quat, Vec3};
 use openusd_rs::{
     gf::{self, Matrix4d},
+    sdf,
     tf::Token,
     usd, usd_geom, vt,
 };
 
+// -------- Errors --------
+/// Errors raised while loading a stage.
+///
+/// `StageOpen` is fatal and returned from [`fetch_stage_usd`]; the others are
+/// reported per prim through [`SceneData::diagnostics`] while loading carries on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UsdLoadError {
+    /// The stage file could not be opened or parsed.
+    StageOpen { path: String, reason: String },
+    /// A prim referenced by the stage is missing or not usable.
+    InvalidPrim { path: String, reason: String },
+    /// Mesh topology is missing or inconsistent.
+    MalformedTopology { path: String, reason: String },
+    /// The prim is geometry the loader does not know how to convert.
+    UnsupportedType { path: String, type_name: String },
+    /// A primvar was left out because it cannot be mapped onto the geometry.
+    DroppedPrimvar {
+        path: String,
+        name: String,
+        reason: String,
+    },
+}
+
+impl UsdLoadError {
+    /// Path of the stage or prim the error refers to.
+    pub fn path(&self) -> &str {
+        match self {
+            UsdLoadError::StageOpen { path, .. }
+            | UsdLoadError::InvalidPrim { path, .. }
+            | UsdLoadError::MalformedTopology { path, .. }
+            | UsdLoadError::UnsupportedType { path, .. }
+            | UsdLoadError::DroppedPrimvar { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for UsdLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UsdLoadError::StageOpen { path, reason } => {
+                write!(f, "failed to open stage {path}: {reason}")
+            }
+            UsdLoadError::InvalidPrim { path, reason } => {
+                write!(f, "invalid prim {path}: {reason}")
+            }
+            UsdLoadError::MalformedTopology { path, reason } => {
+                write!(f, "malformed topology on {path}: {reason}")
+            }
+            UsdLoadError::UnsupportedType { path, type_name } => {
+                write!(f, "unsupported prim type {type_name} on {path}")
+            }
+            UsdLoadError::DroppedPrimvar { path, name, reason } => {
+                write!(f, "dropped primvars:{name} on {path}: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for UsdLoadError {}
+
 // -------- Data structs --------
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PrimvarInterpolation {
@@ -31,389 +100,3263 @@ impl PrimvarInterpolation {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct MeshData {
-    pub positions: Vec<[f32; 3]>,
-    pub face_vertex_counts: Vec<usize>,
-    pub face_vertex_indices: Vec<usize>,
-    pub normals: Option<Vec<[f32; 3]>>,
-    pub normal_indices: Option<Vec<usize>>,
-    pub normal_interpolation: Option<PrimvarInterpolation>,
-    pub uvs: Option<Vec<[f32; 2]>>,
-    pub double_sided: bool,
+/// An authored primvar and its optional index buffer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Primvar<T> {
+    pub values: Vec<T>,
+    pub indices: Option<Vec<usize>>,
+    pub interpolation: PrimvarInterpolation,
+    /// Number of values per element, from the `elementSize` metadata.
+    pub element_size: usize,
+    /// Declared value type, e.g. `texCoord2f[]`.
+    pub type_name: String,
 }
 
-#[derive(Debug, Clone)]
-pub struct MeshInstance {
-    pub mesh_index: usize,
-    pub transform: [[f32; 4]; 4],
+impl<T> Primvar<T> {
+    pub fn element_count(&self) -> usize {
+        self.values.len() / self.element_size.max(1)
+    }
+
+    fn with_values<U>(self, values: Vec<U>) -> Primvar<U> {
+        Primvar {
+            values,
+            indices: self.indices,
+            interpolation: self.interpolation,
+            element_size: self.element_size,
+            type_name: self.type_name,
+        }
+    }
 }
 
-#[derive(Debug, Default, Clone)]
-pub struct SceneData {
-    pub meshes: Vec<MeshData>,
-    pub instances: Vec<MeshInstance>,
+impl<T: Copy> Primvar<T> {
+    /// The single value of a constant primvar.
+    pub fn constant_value(&self) -> Option<T> {
+        if self.interpolation != PrimvarInterpolation::Constant {
+            return None;
+        }
+        let index = self
+            .indices
+            .as_ref()
+            .map_or(Some(0), |i| i.first().copied())?;
+        self.values.get(index * self.element_size).copied()
+    }
 }
 
-// -------- Local transform --------
-fn get_local_transform(prim: &usd::Prim) -> Option<Matrix4d> {
-    if let Some(matrix) = usd_geom::XformOp::get_local_transform_matrix(prim) {
-        return Some(matrix);
+/// A primvar of one of the value types the loader understands.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PrimvarData {
+    Float(Primvar<f32>),
+    Float2(Primvar<[f32; 2]>),
+    Float3(Primvar<[f32; 3]>),
+    Float4(Primvar<[f32; 4]>),
+    Int(Primvar<i32>),
+}
+
+impl PrimvarData {
+    pub fn interpolation(&self) -> PrimvarInterpolation {
+        match self {
+            PrimvarData::Float(p) => p.interpolation,
+            PrimvarData::Float2(p) => p.interpolation,
+            PrimvarData::Float3(p) => p.interpolation,
+            PrimvarData::Float4(p) => p.interpolation,
+            PrimvarData::Int(p) => p.interpolation,
+        }
     }
 
-    let single_tok = Token::new("xformOp:transform");
-    if prim.has_attribute(&single_tok) {
-        let attr = prim.attribute(&single_tok);
-        return Some(attr.get::<Matrix4d>());
+    pub fn element_count(&self) -> usize {
+        match self {
+            PrimvarData::Float(p) => p.element_count(),
+            PrimvarData::Float2(p) => p.element_count(),
+            PrimvarData::Float3(p) => p.element_count(),
+            PrimvarData::Float4(p) => p.element_count(),
+            PrimvarData::Int(p) => p.element_count(),
+        }
     }
 
-    None
-}
+    pub fn type_name(&self) -> &str {
+        match self {
+            PrimvarData::Float(p) => &p.type_name,
+            PrimvarData::Float2(p) => &p.type_name,
+            PrimvarData::Float3(p) => &p.type_name,
+            PrimvarData::Float4(p) => &p.type_name,
+            PrimvarData::Int(p) => &p.type_name,
+        }
+    }
 
-// -------- Mesh data --------
-fn get_mesh_data(prim: &usd::Prim) -> MeshData {
-    let path = prim.path().clone();
-    let stage = prim.stage();
-    let mesh = usd_geom::Mesh::define(&stage, path);
+    pub fn as_float(&self) -> Option<&Primvar<f32>> {
+        match self {
+            PrimvarData::Float(p) => Some(p),
+            _ => None,
+        }
+    }
 
-    // --- doubleSided
-    let double_sided = {
-        let prim = mesh.prim();
-        let double_sided_tok = Token::new("doubleSided");
-        let prop = prim.property(&double_sided_tok);
+    pub fn as_float2(&self) -> Option<&Primvar<[f32; 2]>> {
+        match self {
+            PrimvarData::Float2(p) => Some(p),
+            _ => None,
+        }
+    }
 
-        if prop.is_valid() {
-            if let Some(val) = prop.get_value() {
-                val.get::<bool>().unwrap_or(false)
-            } else {
-                false
-            }
-        } else {
-            false
+    pub fn as_float3(&self) -> Option<&Primvar<[f32; 3]>> {
+        match self {
+            PrimvarData::Float3(p) => Some(p),
+            _ => None,
         }
-    };
+    }
+}
 
-    // --- positions
-    let points_attr = mesh.points_attr();
-    let positions = if points_attr.is_valid() {
-        let arr: vt::Array<gf::Vec3f> = points_attr.get();
-        arr.iter().map(|p| [p.x, p.y, p.z]).collect()
-    } else {
-        Vec::new()
-    };
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshData {
+    pub positions: Vec<[f32; 3]>,
+    pub face_vertex_counts: Vec<usize>,
+    pub face_vertex_indices: Vec<usize>,
+    /// Authored primvars by name, without the `primvars:` prefix.
+    ///
+    /// The `normals` attribute is stored as `normals` and wins over
+    /// `primvars:normals`; `displayColor` is used when no material is bound.
+    pub primvars: HashMap<String, PrimvarData>,
+    pub double_sided: bool,
+    /// Index into [`SceneData::materials`] of the bound material.
+    pub material_index: Option<usize>,
+    pub subdivision: SubdivisionData,
+    /// Faces that are not drawn, sorted.
+    pub hole_indices: Vec<usize>,
+    pub orientation: Orientation,
+}
 
-    // --- faceVertexCounts
-    let fvc_attr = mesh.face_vertex_counts_attr();
-    let face_vertex_counts = if fvc_attr.is_valid() {
-        let arr: vt::Array<i32> = fvc_attr.get();
-        arr.iter().map(|&c| c as usize).collect()
-    } else {
-        Vec::new()
-    };
+/// Winding of the faces seen from the side their normals point to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Orientation {
+    /// Counter-clockwise.
+    #[default]
+    RightHanded,
+    /// Clockwise.
+    LeftHanded,
+}
 
-    // --- faceVertexIndices
-    let fvi_attr = mesh.face_vertex_indices_attr();
-    let face_vertex_indices = if fvi_attr.is_valid() {
-        let arr: vt::Array<i32> = fvi_attr.get();
-        arr.iter().map(|&i| i as usize).collect()
-    } else {
-        Vec::new()
-    };
+/// `subdivisionScheme` of a mesh; the default draws the faces as authored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SubdivisionScheme {
+    #[default]
+    None,
+    CatmullClark,
+    Loop,
+    Bilinear,
+}
 
-    // --- normals / primvars:normals
-    let normals_token = Token::new("normals");
-    let normals_attr = mesh.normals_attr();
+impl SubdivisionScheme {
+    fn from_token(token: &str) -> Option<Self> {
+        match token {
+            "none" => Some(SubdivisionScheme::None),
+            "catmullClark" => Some(SubdivisionScheme::CatmullClark),
+            "loop" => Some(SubdivisionScheme::Loop),
+            "bilinear" => Some(SubdivisionScheme::Bilinear),
+            _ => None,
+        }
+    }
+}
 
-    let (normals, normal_indices, normal_interpolation) = if normals_attr.is_valid() {
-        // direct normals attr
-        let arr: vt::Array<gf::Vec3f> = normals_attr.get();
-        let normals = if !arr.is_empty() {
-            Some(arr.iter().map(|n| [n.x, n.y, n.z]).collect())
-        } else {
-            None
-        };
+/// How the limit surface treats boundary edges and corners.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum InterpolateBoundary {
+    None,
+    EdgeOnly,
+    #[default]
+    EdgeAndCorner,
+}
 
-        let interpolation = normals_attr
-            .metadata::<Token>(&Token::new("interpolation"))
-            .map(|token| PrimvarInterpolation::from_token(token.as_str()));
+impl InterpolateBoundary {
+    fn from_token(token: &str) -> Option<Self> {
+        match token {
+            "none" => Some(InterpolateBoundary::None),
+            "edgeOnly" => Some(InterpolateBoundary::EdgeOnly),
+            "edgeAndCorner" => Some(InterpolateBoundary::EdgeAndCorner),
+            _ => None,
+        }
+    }
+}
 
-        let indices_token = Token::new("normals:indices");
-        let idx_attr = mesh.prim().attribute(&indices_token);
-        let normal_indices = if idx_attr.is_valid() {
-            let idx_arr: vt::Array<i32> = idx_attr.get();
-            if !idx_arr.is_empty() {
-                Some(idx_arr.iter().map(|&i| i as usize).collect())
-            } else {
-                None
-            }
-        } else {
-            None
-        };
+/// Subdivision surface settings and sharpness tags of a mesh.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SubdivisionData {
+    pub scheme: SubdivisionScheme,
+    pub interpolate_boundary: InterpolateBoundary,
+    /// Creased edges by their two points, with one sharpness per edge.
+    pub crease_edges: Vec<([usize; 2], f32)>,
+    /// Sharp corners by point index.
+    pub corners: Vec<(usize, f32)>,
+}
 
-        (normals, normal_indices, interpolation)
-    } else {
-        // primvar normals
-        let attr = mesh.primvar(&normals_token);
-        if attr.is_valid() {
-            let normals = attr
-                .get_value()
-                .and_then(|value| value.get::<vt::Array<gf::Vec3f>>())
-                .and_then(|arr| {
-                    if arr.is_empty() {
-                        None
-                    } else {
-                        Some(arr.iter().map(|n| [n.x, n.y, n.z]).collect())
-                    }
-                });
-
-            let interpolation = attr
-                .metadata::<Token>(&Token::new("interpolation"))
-                .map(|token| PrimvarInterpolation::from_token(token.as_str()));
-
-            let indices_token = Token::new("primvars:normals:indices");
-            let idx_attr = mesh.prim().attribute(&indices_token);
-            let normal_indices = if idx_attr.is_valid() {
-                let idx_arr: vt::Array<i32> = idx_attr.get();
-                if !idx_arr.is_empty() {
-                    Some(idx_arr.iter().map(|&i| i as usize).collect())
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
+/// `UsdPreviewSurface` inputs of a bound material, colors are linear.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaterialData {
+    /// Path of the Material prim.
+    pub path: String,
+    pub diffuse_color: [f32; 3],
+    pub emissive_color: [f32; 3],
+    pub metallic: f32,
+    pub roughness: f32,
+    pub opacity: f32,
+    /// Alpha cutoff, `0.0` means opacity blends instead of masking.
+    pub opacity_threshold: f32,
+    pub ior: f32,
+    /// Tangent space normal used when no normal map is connected.
+    pub normal: [f32; 3],
+    pub textures: MaterialTextures,
+}
 
-            (normals, normal_indices, interpolation)
-        } else {
-            (None, None, None)
+/// `UsdUVTexture` shaders connected to the preview surface inputs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MaterialTextures {
+    pub diffuse_color: Option<TextureData>,
+    pub emissive_color: Option<TextureData>,
+    pub metallic: Option<TextureData>,
+    pub roughness: Option<TextureData>,
+    pub opacity: Option<TextureData>,
+    pub normal: Option<TextureData>,
+    pub occlusion: Option<TextureData>,
+}
+
+/// How a `BasisCurves` prim joins its control points, from `type` and `basis`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CurveBasis {
+    Linear,
+    #[default]
+    Bezier,
+    Bspline,
+    CatmullRom,
+}
+
+impl CurveBasis {
+    pub fn from_tokens(curve_type: &str, basis: &str) -> Option<Self> {
+        match (curve_type, basis) {
+            ("linear", _) => Some(CurveBasis::Linear),
+            ("cubic", "bezier") => Some(CurveBasis::Bezier),
+            ("cubic", "bspline") => Some(CurveBasis::Bspline),
+            ("cubic", "catmullRom") => Some(CurveBasis::CatmullRom),
+            _ => None,
         }
-    };
+    }
+}
 
-    // --- UVs
-    let uv_attr = mesh.primvar(&Token::new("st"));
-    let uvs = if uv_attr.is_valid() {
-        uv_attr
-            .get_value()
-            .and_then(|val| val.get::<vt::Array<gf::Vec2f>>())
-            .and_then(|arr| {
-                if arr.is_empty() {
-                    None
-                } else {
-                    Some(arr.iter().map(|uv| [uv.x, uv.y]).collect())
-                }
-            })
-    } else {
-        None
-    };
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CurveWrap {
+    #[default]
+    Nonperiodic,
+    Periodic,
+    /// Passes through its end points, for B-spline and Catmull-Rom curves.
+    Pinned,
+}
 
-    MeshData {
-        positions,
-        face_vertex_counts,
-        face_vertex_indices,
-        normals,
-        normal_indices,
-        normal_interpolation,
-        uvs,
-        double_sided,
+impl CurveWrap {
+    pub fn from_token(token: &str) -> Option<Self> {
+        match token {
+            "nonperiodic" => Some(CurveWrap::Nonperiodic),
+            "periodic" => Some(CurveWrap::Periodic),
+            "pinned" => Some(CurveWrap::Pinned),
+            _ => None,
+        }
     }
 }
 
-#[cfg_attr(not(test), allow(dead_code))]
-fn matrix4d_to_mat4(matrix: &Matrix4d) -> Mat4 {
-    let src = matrix.as_array();
-    let mut cols = [0.0f32; 16];
-    for col in 0..4 {
-        for row in 0..4 {
-            cols[col * 4 + row] = src[row][col] as f32;
+/// A `BasisCurves` prim, control points in prim space.
+///
+/// Curves are not shared between instances; prototypes skip them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurvesData {
+    pub path: String,
+    pub points: Vec<[f32; 3]>,
+    pub curve_vertex_counts: Vec<usize>,
+    pub basis: CurveBasis,
+    pub wrap: CurveWrap,
+    /// Diameters; tubes when `normals` is `None`, ribbons otherwise.
+    pub widths: Option<Primvar<f32>>,
+    /// Facing of ribbons.
+    pub normals: Option<Primvar<[f32; 3]>>,
+    pub primvars: HashMap<String, PrimvarData>,
+    pub material_index: Option<usize>,
+    pub transform: InstanceTransform,
+}
+
+/// A `Points` prim, positions in prim space.
+///
+/// Like curves, points are not shared between instances.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointsData {
+    pub path: String,
+    pub positions: Vec<[f32; 3]>,
+    /// Diameters.
+    pub widths: Option<Primvar<f32>>,
+    pub primvars: HashMap<String, PrimvarData>,
+    pub material_index: Option<usize>,
+    pub transform: InstanceTransform,
+}
+
+/// A `UsdUVTexture` feeding one material input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureData {
+    /// Image file, resolved against the layer that authored `inputs:file`.
+    pub file: String,
+    /// Texture output the material input is connected to.
+    pub channel: TextureChannel,
+    pub wrap_s: TextureWrap,
+    pub wrap_t: TextureWrap,
+    pub color_space: SourceColorSpace,
+    /// Applied to every texel as `texel * scale + bias`.
+    pub scale: [f32; 4],
+    pub bias: [f32; 4],
+    /// Returned when the file cannot be read.
+    pub fallback: [f32; 4],
+}
+
+/// Outputs of a `UsdUVTexture`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureChannel {
+    R,
+    G,
+    B,
+    A,
+    Rgb,
+}
+
+impl TextureChannel {
+    fn from_output(name: &str) -> Option<Self> {
+        match name {
+            "outputs:r" => Some(TextureChannel::R),
+            "outputs:g" => Some(TextureChannel::G),
+            "outputs:b" => Some(TextureChannel::B),
+            "outputs:a" => Some(TextureChannel::A),
+            "outputs:rgb" => Some(TextureChannel::Rgb),
+            _ => None,
         }
     }
-    Mat4::from_cols_array(&cols)
 }
 
-fn mat4_to_matrix4d(mat: Mat4) -> Matrix4d {
-    let cols = mat.to_cols_array();
-    let mut data = [[0.0f64; 4]; 4];
-    for col in 0..4 {
-        for row in 0..4 {
-            data[row][col] = cols[col * 4 + row] as f64;
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextureWrap {
+    Black,
+    Clamp,
+    Repeat,
+    Mirror,
+    /// Defer to the image file, which we treat as `Black`.
+    #[default]
+    UseMetadata,
+}
+
+impl TextureWrap {
+    fn from_token(token: &str) -> Self {
+        match token {
+            "black" => TextureWrap::Black,
+            "clamp" => TextureWrap::Clamp,
+            "repeat" => TextureWrap::Repeat,
+            "mirror" => TextureWrap::Mirror,
+            _ => TextureWrap::UseMetadata,
         }
     }
-    Matrix4d::from_array(data)
 }
 
-fn matrix4d_to_f32_array(matrix: &Matrix4d) -> [[f32; 4]; 4] {
-    let src = matrix.as_array();
-    let mut out = [[0.0f32; 4]; 4];
-    for row in 0..4 {
-        for col in 0..4 {
-            out[row][col] = src[row][col] as f32;
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SourceColorSpace {
+    /// sRGB for color inputs, raw for everything else.
+    #[default]
+    Auto,
+    Raw,
+    Srgb,
+}
+
+impl SourceColorSpace {
+    fn from_token(token: &str) -> Self {
+        match token {
+            "raw" => SourceColorSpace::Raw,
+            "sRGB" => SourceColorSpace::Srgb,
+            _ => SourceColorSpace::Auto,
         }
     }
-    out
 }
 
-fn make_trs_matrix(pos: [f32; 3], rot: [f32; 4], scale: [f32; 3]) -> Matrix4d {
-    let translation = Vec3::from_array(pos);
-    let mut rotation = Quat::from_xyzw(rot[0], rot[1], rot[2], rot[3]);
-    if rotation.length_squared() <= f32::EPSILON {
-        rotation = Quat::IDENTITY;
-    } else {
-        rotation = rotation.normalize();
+impl Default for MaterialData {
+    /// The `UsdPreviewSurface` fallback values.
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            diffuse_color: [0.18, 0.18, 0.18],
+            emissive_color: [0.0, 0.0, 0.0],
+            metallic: 0.0,
+            roughness: 0.5,
+            opacity: 1.0,
+            opacity_threshold: 0.0,
+            ior: 1.5,
+            normal: [0.0, 0.0, 1.0],
+            textures: MaterialTextures::default(),
+        }
     }
-    let scale = Vec3::from_array(scale);
+}
 
-    let mat = Mat4::from_scale_rotation_translation(scale, rotation, translation);
-    mat4_to_matrix4d(mat)
+/// Time at which attribute values are read.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TimeCode {
+    /// The authored default value, ignoring time samples.
+    #[default]
+    Default,
+    /// A time code on the stage timeline.
+    At(f64),
 }
 
-// -------- Scene builder --------
-struct SceneBuilder {
-    data: SceneData,
-    mesh_lookup: HashMap<String, usize>,
+/// Values of the `purpose` attribute.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Purpose {
+    #[default]
+    Default,
+    Render,
+    Proxy,
+    Guide,
 }
 
-impl SceneBuilder {
-    fn new() -> Self {
-        Self {
-            data: SceneData::default(),
-            mesh_lookup: HashMap::new(),
+impl Purpose {
+    fn from_token(token: &str) -> Option<Self> {
+        match token {
+            "default" => Some(Purpose::Default),
+            "render" => Some(Purpose::Render),
+            "proxy" => Some(Purpose::Proxy),
+            "guide" => Some(Purpose::Guide),
+            _ => None,
         }
     }
+}
 
-    fn get_or_insert_mesh(&mut self, prim: &usd::Prim) -> usize {
-        let key = prim.path().to_string();
-        if let Some(&idx) = self.mesh_lookup.get(&key) {
-            return idx;
-        }
+/// What the loader reads from a stage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadOptions {
+    /// Time at which attributes are read.
+    pub time_code: TimeCode,
+    /// Geometry with any other purpose is skipped.
+    pub purposes: Vec<Purpose>,
+    /// Merge meshes with identical content that live at different paths.
+    pub dedup_meshes: bool,
+    /// Convert the stage's `upAxis` and `metersPerUnit` to Bevy's Y-up
+    /// meters at the root, see [`StageMetadata::root_correction`].
+    ///
+    /// Stages without `metersPerUnit` are read as centimetres, USD's
+    /// fallback, and come out a hundred times smaller than their raw
+    /// coordinates. Turn this off to keep scene units as they are.
+    pub correct_units_and_axis: bool,
+    /// Segments around a full circle when tessellating implicit surfaces
+    /// such as spheres and cylinders.
+    pub gprim_segments: u32,
+}
 
-        let mesh_data = get_mesh_data(prim);
-        let index = self.data.meshes.len();
-        self.data.meshes.push(mesh_data);
-        self.mesh_lookup.insert(key, index);
-        index
+impl Default for LoadOptions {
+    /// Final render geometry at the default time.
+    fn default() -> Self {
+        Self {
+            time_code: TimeCode::Default,
+            purposes: vec![Purpose::Default, Purpose::Render],
+            dedup_meshes: false,
+            correct_units_and_axis: true,
+            gprim_segments: 24,
+        }
     }
+}
 
-    fn push_instance(&mut self, mesh_index: usize, xf: &Matrix4d) {
-        let transform = matrix4d_to_f32_array(xf);
-        self.data.instances.push(MeshInstance {
-            mesh_index,
-            transform,
-        });
+/// Timeline and scene unit metadata from the stage's root layer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageMetadata {
+    pub start_time_code: Option<f64>,
+    pub end_time_code: Option<f64>,
+    pub time_codes_per_second: f64,
+    pub up_axis: UpAxis,
+    pub meters_per_unit: f64,
+}
+
+impl Default for StageMetadata {
+    fn default() -> Self {
+        Self {
+            start_time_code: None,
+            end_time_code: None,
+            time_codes_per_second: DEFAULT_TIME_CODES_PER_SECOND,
+            up_axis: UpAxis::Y,
+            meters_per_unit: DEFAULT_METERS_PER_UNIT,
+        }
     }
+}
 
-    fn into_scene(self) -> SceneData {
-        self.data
+impl StageMetadata {
+    /// Transform taking stage space to Bevy's Y-up meters.
+    pub fn root_correction(&self) -> DMat4 {
+        let rotation = match self.up_axis {
+            UpAxis::Y => DMat4::IDENTITY,
+            // Z-up turns -90 degrees about X so +Z lands on +Y
+            UpAxis::Z => DMat4::from_rotation_x(-std::f64::consts::FRAC_PI_2),
+        };
+        DMat4::from_scale(DVec3::splat(self.meters_per_unit)) * rotation
     }
 }
 
-// -------- Recursively expand prims --------
-fn expand_prim(
-    stage: &usd::Stage,
-    prim: &usd::Prim,
-    parent_xf: &Matrix4d,
-    scene: &mut SceneBuilder,
-) {
-    let local = get_local_transform(prim)
-        .map(|m| m.transpose())
-        .unwrap_or_else(Matrix4d::identity);
-    let world_xf = parent_xf.post_mult(&local);
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UpAxis {
+    #[default]
+    Y,
+    Z,
+}
 
-    match prim.type_name().as_str() {
-        "Mesh" => {
-            let mesh_index = scene.get_or_insert_mesh(prim);
-            scene.push_instance(mesh_index, &world_xf);
-        }
-        "PointInstancer" => {
-            let inst = usd_geom::PointInstancer::define(&stage, prim.path().clone());
+/// USD's fallback when neither `timeCodesPerSecond` nor `framesPerSecond` is authored.
+const DEFAULT_TIME_CODES_PER_SECOND: f64 = 24.0;
 
-            let indices: Vec<usize> = inst
-                .proto_indices_attr()
-                .get::<vt::Array<i32>>()
-                .iter()
-                .map(|&i| i as usize)
-                .collect();
+/// USD's fallback `metersPerUnit`, centimetres.
+const DEFAULT_METERS_PER_UNIT: f64 = 0.01;
 
-            let positions: Vec<[f32; 3]> = inst
-                .positions_attr()
-                .get::<vt::Array<gf::Vec3f>>()
-                .iter()
-                .map(|p| [p.x, p.y, p.z])
-                .collect();
+/// World transform of an instance.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstanceTransform {
+    Static([[f32; 4]; 4]),
+    /// Index into [`SceneData::transform_tracks`].
+    Animated(usize),
+}
 
-            let scales: Vec<[f32; 3]> = inst
-                .scales_attr()
-                .get::<vt::Array<gf::Vec3f>>()
-                .iter()
-                .map(|p| [p.x, p.y, p.z])
-                .collect();
-
-            let rotations: Vec<[f32; 4]> = match inst.orientations_attr().get_value() {
-                Some(val) => {
-                    if let Some(arr) = val.get::<vt::Array<gf::Quatf>>() {
-                        arr.iter().map(|q| [q.i, q.j, q.k, q.w]).collect()
-                    } else if let Some(arr) = val.get::<vt::Array<gf::Quatd>>() {
-                        arr.iter()
-                            .map(|q| [q.i as f32, q.j as f32, q.k as f32, q.w as f32])
-                            .collect()
-                    } else if let Some(arr) = val.get::<vt::Array<gf::Quath>>() {
-                        arr.iter()
-                            .map(|q| [q.i.into(), q.j.into(), q.k.into(), q.w.into()])
-                            .collect()
-                    } else {
-                        vec![]
-                    }
-                }
-                None => vec![],
-            };
+#[derive(Debug, Clone)]
+pub struct MeshInstance {
+    pub mesh_index: usize,
+    pub transform: InstanceTransform,
+    /// Index into [`SceneData::materials`] of the material bound to this instance.
+    pub material_index: Option<usize>,
+}
 
-            let targets = inst.prototypes_rel().targets();
-            for (proto_idx, path) in targets.iter().enumerate() {
-                let proto = stage.prim_at_path(path.clone());
-                for (point_idx, &pi) in indices.iter().enumerate() {
-                    if pi == proto_idx {
-                        let pos = *positions.get(point_idx).unwrap_or(&[0.0, 0.0, 0.0]);
-                        let scale = *scales.get(point_idx).unwrap_or(&[1.0, 1.0, 1.0]);
-                        let rot = *rotations.get(point_idx).unwrap_or(&[0.0, 0.0, 0.0, 1.0]);
-                        let xf = make_trs_matrix(pos, rot, scale);
-                        let new_xf = world_xf.post_mult(&xf);
-                        expand_prim(stage, &proto, &new_xf, scene);
-                    }
-                }
-            }
-        }
-        _ => {
-            for child in prim.children() {
-                expand_prim(stage, &child, &world_xf, scene);
-            }
-        }
-    }
+/// World transforms of an animated instance sampled over the stage timeline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransformTrack {
+    /// Ascending time codes, one per sample.
+    pub times: Vec<f64>,
+    pub transforms: Vec<[[f32; 4]; 4]>,
 }
 
-// -------- Entry point --------
-pub fn fetch_stage_usd(stagep: &str) -> SceneData {
-    let stage = usd::Stage::open(stagep);
-    let mut builder = SceneBuilder::new();
-
-    expand_prim(
-        &stage,
-        &stage.pseudo_root(),
-        &Matrix4d::identity(),
-        &mut builder,
-    );
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CameraProjection {
+    #[default]
+    Perspective,
+    Orthographic,
+}
 
-    builder.into_scene()
+/// A `UsdGeomCamera`.
+///
+/// Focal length and apertures are in tenths of a scene unit (millimetres by
+/// convention), clipping range and focus distance in scene units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraData {
+    pub path: String,
+    pub projection: CameraProjection,
+    pub focal_length: f32,
+    pub horizontal_aperture: f32,
+    pub vertical_aperture: f32,
+    /// Near and far clipping distances.
+    pub clipping_range: [f32; 2],
+    /// Zero when no focus distance is authored.
+    pub focus_distance: f32,
+    pub transform: InstanceTransform,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+impl CameraData {
+    /// Vertical field of view in radians.
+    pub fn vertical_fov(&self) -> f32 {
+        2.0 * (self.vertical_aperture / (2.0 * self.focal_length)).atan()
+    }
 
-    fn approx_eq(a: f32, b: f32) -> bool {
-        (a - b).abs() < 1e-4
+    /// Width over height of the film back.
+    pub fn aspect_ratio(&self) -> f32 {
+        self.horizontal_aperture / self.vertical_aperture
     }
+}
 
-    #[test]
-    fn make_trs_matches_glam() {
+/// Shape of a `UsdLux` light, sizes in scene units.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LightKind {
+    /// Angular diameter in degrees.
+    Distant {
+        angle: f32,
+    },
+    Sphere {
+        radius: f32,
+    },
+    Disk {
+        radius: f32,
+    },
+    Rect {
+        width: f32,
+        height: f32,
+    },
+    /// Lat-long environment, resolved like [`TextureData::file`].
+    Dome {
+        texture: Option<String>,
+    },
+}
+
+/// `ShapingAPI` cone restricting where a light shines.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LightCone {
+    /// Half-angle in degrees.
+    pub angle: f32,
+    /// 0 is a hard edge, 1 fades from the centre.
+    pub softness: f32,
+}
+
+/// A `UsdLux` light. Lights shine along their local -Z axis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LightData {
+    pub path: String,
+    pub kind: LightKind,
+    pub intensity: f32,
+    pub exposure: f32,
+    pub color: [f32; 3],
+    /// Kelvin, set when `enableColorTemperature` is on.
+    pub color_temperature: Option<f32>,
+    pub cone: Option<LightCone>,
+    pub transform: InstanceTransform,
+}
+
+impl LightData {
+    /// `intensity` scaled by `2^exposure`.
+    pub fn scaled_intensity(&self) -> f32 {
+        self.intensity * self.exposure.exp2()
+    }
+
+    /// `color` tinted by the color temperature, linear RGB.
+    pub fn linear_color(&self) -> [f32; 3] {
+        let tint = self.color_temperature.map_or([1.0; 3], blackbody_rgb);
+        [
+            self.color[0] * tint[0],
+            self.color[1] * tint[1],
+            self.color[2] * tint[2],
+        ]
+    }
+}
+
+/// Linear sRGB color of a black body at `kelvin`, normalized to unit luminance.
+///
+/// Uses the Kim et al. cubic fit of the Planckian locus, valid 1667K to 25000K.
+fn blackbody_rgb(kelvin: f32) -> [f32; 3] {
+    let t = f64::from(kelvin.clamp(1667.0, 25000.0));
+    let x = if t <= 4000.0 {
+        -0.2661239e9 / t.powi(3) - 0.2343589e6 / t.powi(2) + 0.8776956e3 / t + 0.179910
+    } else {
+        -3.0258469e9 / t.powi(3) + 2.1070379e6 / t.powi(2) + 0.2226347e3 / t + 0.240390
+    };
+    let y = if t <= 2222.0 {
+        -1.1063814 * x.powi(3) - 1.34811020 * x.powi(2) + 2.18555832 * x - 0.20219683
+    } else if t <= 4000.0 {
+        -0.9549476 * x.powi(3) - 1.37418593 * x.powi(2) + 2.09137015 * x - 0.16748867
+    } else {
+        3.0817580 * x.powi(3) - 5.87338670 * x.powi(2) + 3.75112997 * x - 0.37001483
+    };
+
+    // xyY with Y = 1 to linear sRGB
+    let (cx, cz) = (x / y, (1.0 - x - y) / y);
+    let rgb = [
+        3.2404542 * cx - 1.5371385 - 0.4985314 * cz,
+        -0.9692660 * cx + 1.8760108 + 0.0415560 * cz,
+        0.0556434 * cx - 0.2040259 + 1.0572252 * cz,
+    ]
+    .map(|c| c.max(0.0));
+    let luminance = 0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2];
+    rgb.map(|c| (c / luminance) as f32)
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SceneData {
+    pub meshes: Vec<MeshData>,
+    pub instances: Vec<MeshInstance>,
+    pub materials: Vec<MaterialData>,
+    pub transform_tracks: Vec<TransformTrack>,
+    pub curves: Vec<CurvesData>,
+    pub points: Vec<PointsData>,
+    pub cameras: Vec<CameraData>,
+    pub lights: Vec<LightData>,
+    pub metadata: StageMetadata,
+    /// Time the scene was evaluated at.
+    pub time_code: TimeCode,
+    /// Non-fatal problems hit while loading; prims listed here were skipped.
+    pub diagnostics: Vec<UsdLoadError>,
+    /// Set when [`LoadOptions::dedup_meshes`] was on.
+    pub dedup: Option<DedupReport>,
+}
+
+/// Outcome of merging meshes with identical content.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DedupReport {
+    pub meshes_before: usize,
+    pub meshes_after: usize,
+    /// Approximate size of the `MeshData` that was dropped.
+    pub bytes_saved: usize,
+}
+
+impl fmt::Display for DedupReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "merged {} duplicate meshes ({} -> {}), saving about {:.1} KiB",
+            self.meshes_before - self.meshes_after,
+            self.meshes_before,
+            self.meshes_after,
+            self.bytes_saved as f64 / 1024.0
+        )
+    }
+}
+
+// -------- Attribute reads --------
+fn attr_value(attr: &usd::Attribute, time: TimeCode) -> Option<vt::Value> {
+    match time {
+        TimeCode::Default => attr.get_value(),
+        TimeCode::At(t) => attr.get_value_at(t),
+    }
+}
+
+fn read_stage_metadata(stage: &usd::Stage) -> StageMetadata {
+    let read = |name: &str| stage.metadata::<f64>(&Token::new(name));
+    let up_axis = match stage.metadata::<Token>(&Token::new("upAxis")) {
+        Some(token) if token.as_str() == "Z" => UpAxis::Z,
+        _ => UpAxis::Y,
+    };
+    StageMetadata {
+        start_time_code: read("startTimeCode"),
+        end_time_code: read("endTimeCode"),
+        time_codes_per_second: read("timeCodesPerSecond")
+            .or_else(|| read("framesPerSecond"))
+            .unwrap_or(DEFAULT_TIME_CODES_PER_SECOND),
+        up_axis,
+        meters_per_unit: read("metersPerUnit")
+            .filter(|&mpu| mpu > 0.0)
+            .unwrap_or(DEFAULT_METERS_PER_UNIT),
+    }
+}
+
+// -------- Local transform --------
+/// Marker that makes a prim ignore its parents' transforms.
+const RESET_XFORM_STACK: &str = "!resetXformStack!";
+const INVERT_PREFIX: &str = "!invert!";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum XformOpType {
+    Translate,
+    Scale,
+    RotateX,
+    RotateY,
+    RotateZ,
+    /// Euler rotation; the axes are listed in the order they are applied.
+    RotateEuler([EulerAxis; 3]),
+    Orient,
+    Transform,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EulerAxis {
+    X,
+    Y,
+    Z,
+}
+
+impl XformOpType {
+    fn from_name(name: &str) -> Option<Self> {
+        use EulerAxis::*;
+        Some(match name {
+            "translate" => XformOpType::Translate,
+            "scale" => XformOpType::Scale,
+            "rotateX" => XformOpType::RotateX,
+            "rotateY" => XformOpType::RotateY,
+            "rotateZ" => XformOpType::RotateZ,
+            "rotateXYZ" => XformOpType::RotateEuler([X, Y, Z]),
+            "rotateXZY" => XformOpType::RotateEuler([X, Z, Y]),
+            "rotateYXZ" => XformOpType::RotateEuler([Y, X, Z]),
+            "rotateYZX" => XformOpType::RotateEuler([Y, Z, X]),
+            "rotateZXY" => XformOpType::RotateEuler([Z, X, Y]),
+            "rotateZYX" => XformOpType::RotateEuler([Z, Y, X]),
+            "orient" => XformOpType::Orient,
+            "transform" => XformOpType::Transform,
+            _ => return None,
+        })
+    }
+}
+
+/// One entry of `xformOpOrder`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct XformOp {
+    op_type: XformOpType,
+    /// Attribute holding the op value, e.g. `xformOp:translate:pivot`.
+    attr_name: String,
+    inverse: bool,
+}
+
+/// `xformOpOrder` split into the reset flag and the ops that apply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct XformOpOrder {
+    resets_xform_stack: bool,
+    ops: Vec<XformOp>,
+}
+
+fn parse_xform_op(entry: &str) -> Result<XformOp, String> {
+    let (inverse, attr_name) = match entry.strip_prefix(INVERT_PREFIX) {
+        Some(rest) => (true, rest),
+        None => (false, entry),
+    };
+    let op_type = attr_name
+        .strip_prefix("xformOp:")
+        .map(|rest| rest.split(':').next().unwrap_or(rest))
+        .and_then(XformOpType::from_name)
+        .ok_or_else(|| format!("unknown xformOp {entry}"))?;
+
+    Ok(XformOp {
+        op_type,
+        attr_name: attr_name.to_string(),
+        inverse,
+    })
+}
+
+/// Parse `xformOpOrder`; ops before the last `!resetXformStack!` are ignored.
+fn parse_xform_op_order<'a>(
+    order: impl IntoIterator<Item = &'a str>,
+) -> Result<XformOpOrder, String> {
+    let mut parsed = XformOpOrder {
+        resets_xform_stack: false,
+        ops: Vec::new(),
+    };
+    for entry in order {
+        if entry == RESET_XFORM_STACK {
+            parsed.resets_xform_stack = true;
+            parsed.ops.clear();
+        } else {
+            parsed.ops.push(parse_xform_op(entry)?);
+        }
+    }
+    Ok(parsed)
+}
+
+/// Authored value of an xformOp attribute.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum XformOpValue {
+    Scalar(f64),
+    Vec3([f64; 3]),
+    /// Imaginary parts first, real part last.
+    Quat([f64; 4]),
+    /// Row-major matrix in USD's row-vector convention.
+    Matrix([[f64; 4]; 4]),
+}
+
+fn euler_axis_matrix(axis: EulerAxis, degrees: f64) -> DMat4 {
+    let radians = degrees.to_radians();
+    match axis {
+        EulerAxis::X => DMat4::from_rotation_x(radians),
+        EulerAxis::Y => DMat4::from_rotation_y(radians),
+        EulerAxis::Z => DMat4::from_rotation_z(radians),
+    }
+}
+
+/// Column-vector matrix for a single op. Fails when the value has the wrong
+/// shape or an inverted op has no inverse.
+fn xform_op_matrix(op: &XformOp, value: XformOpValue) -> Result<DMat4, String> {
+    let matrix = match (op.op_type, value) {
+        (XformOpType::Translate, XformOpValue::Vec3(v)) => {
+            DMat4::from_translation(DVec3::from_array(v))
+        }
+        (XformOpType::Scale, XformOpValue::Vec3(v)) => DMat4::from_scale(DVec3::from_array(v)),
+        (XformOpType::RotateX, XformOpValue::Scalar(deg)) => euler_axis_matrix(EulerAxis::X, deg),
+        (XformOpType::RotateY, XformOpValue::Scalar(deg)) => euler_axis_matrix(EulerAxis::Y, deg),
+        (XformOpType::RotateZ, XformOpValue::Scalar(deg)) => euler_axis_matrix(EulerAxis::Z, deg),
+        (XformOpType::RotateEuler(axes), XformOpValue::Vec3(v)) => {
+            axes.iter().fold(DMat4::IDENTITY, |acc, &axis| {
+                let degrees = match axis {
+                    EulerAxis::X => v[0],
+                    EulerAxis::Y => v[1],
+                    EulerAxis::Z => v[2],
+                };
+                euler_axis_matrix(axis, degrees) * acc
+            })
+        }
+        (XformOpType::Orient, XformOpValue::Quat([i, j, k, w])) => {
+            let q = DQuat::from_xyzw(i, j, k, w);
+            if q.length_squared() <= f64::EPSILON {
+                DMat4::IDENTITY
+            } else {
+                DMat4::from_quat(q.normalize())
+            }
+        }
+        (XformOpType::Transform, XformOpValue::Matrix(rows)) => {
+            // USD stores row vectors, so its rows are our columns
+            DMat4::from_cols_array_2d(&rows)
+        }
+        _ => return Err(format!("{} has a value of the wrong type", op.attr_name)),
+    };
+    if !op.inverse {
+        return Ok(matrix);
+    }
+
+    // a zero scale flattens space, its inverse would be all NaN
+    let inverse = matrix.inverse();
+    if matrix.determinant() == 0.0 || !inverse.is_finite() {
+        return Err(format!("!invert!{} is singular", op.attr_name));
+    }
+    Ok(inverse)
+}
+
+/// Compose ops in `xformOpOrder` order, the last op is applied to points first.
+fn compose_xform_ops(ops: &[(XformOp, XformOpValue)]) -> Result<DMat4, String> {
+    ops.iter().try_fold(DMat4::IDENTITY, |acc, (op, value)| {
+        Ok(acc * xform_op_matrix(op, *value)?)
+    })
+}
+
+fn read_xform_op_value(attr: &usd::Attribute, time: TimeCode) -> Option<XformOpValue> {
+    let value = attr_value(attr, time)?;
+    if let Some(v) = value.get::<f64>() {
+        Some(XformOpValue::Scalar(v))
+    } else if let Some(v) = value.get::<f32>() {
+        Some(XformOpValue::Scalar(v as f64))
+    } else if let Some(v) = value.get::<gf::Vec3d>() {
+        Some(XformOpValue::Vec3([v.x, v.y, v.z]))
+    } else if let Some(v) = value.get::<gf::Vec3f>() {
+        Some(XformOpValue::Vec3([v.x as f64, v.y as f64, v.z as f64]))
+    } else if let Some(q) = value.get::<gf::Quatd>() {
+        Some(XformOpValue::Quat([q.i, q.j, q.k, q.w]))
+    } else if let Some(q) = value.get::<gf::Quatf>() {
+        Some(XformOpValue::Quat([
+            q.i as f64, q.j as f64, q.k as f64, q.w as f64,
+        ]))
+    } else if let Some(q) = value.get::<gf::Quath>() {
+        let q: [f32; 4] = [q.i.into(), q.j.into(), q.k.into(), q.w.into()];
+        Some(XformOpValue::Quat(q.map(|c| c as f64)))
+    } else {
+        value
+            .get::<Matrix4d>()
+            .map(|m| XformOpValue::Matrix(matrix4d_rows(&m)))
+    }
+}
+
+/// Local transform of a prim in column-vector convention.
+struct LocalTransform {
+    matrix: Matrix4d,
+    resets_xform_stack: bool,
+}
+
+fn get_local_transform(
+    prim: &usd::Prim,
+    time: TimeCode,
+) -> Result<Option<LocalTransform>, UsdLoadError> {
+    let invalid = |reason: String| UsdLoadError::InvalidPrim {
+        path: prim.path().to_string(),
+        reason,
+    };
+
+    let order_attr = prim.attribute(&Token::new("xformOpOrder"));
+    let order: Option<vt::Array<Token>> = if order_attr.is_valid() {
+        order_attr.get_value().and_then(|val| val.get())
+    } else {
+        None
+    };
+
+    let Some(order) = order else {
+        // no op order authored, accept a lone matrix op
+        let single_tok = Token::new("xformOp:transform");
+        if prim.has_attribute(&single_tok) {
+            let attr = prim.attribute(&single_tok);
+            let Some(matrix) = attr_value(&attr, time).and_then(|val| val.get::<Matrix4d>()) else {
+                return Err(invalid("xformOp:transform has no usable value".to_string()));
+            };
+            return Ok(Some(LocalTransform {
+                matrix: matrix.transpose(),
+                resets_xform_stack: false,
+            }));
+        }
+        return Ok(None);
+    };
+
+    let parsed = parse_xform_op_order(order.iter().map(|tok| tok.as_str())).map_err(invalid)?;
+    let mut ops = Vec::with_capacity(parsed.ops.len());
+    for op in parsed.ops {
+        let attr = prim.attribute(&Token::new(op.attr_name.as_str()));
+        let value = if attr.is_valid() {
+            read_xform_op_value(&attr, time)
+        } else {
+            None
+        };
+        let value =
+            value.ok_or_else(|| invalid(format!("{} has no usable value", op.attr_name)))?;
+        ops.push((op, value));
+    }
+
+    let matrix = compose_xform_ops(&ops).map_err(invalid)?;
+    Ok(Some(LocalTransform {
+        matrix: dmat4_to_matrix4d(matrix),
+        resets_xform_stack: parsed.resets_xform_stack,
+    }))
+}
+
+/// Whether any op of the prim's xform stack has more than one time sample.
+fn has_animated_xform(prim: &usd::Prim) -> bool {
+    let order_attr = prim.attribute(&Token::new("xformOpOrder"));
+    let names: Vec<String> = if order_attr.is_valid() {
+        order_attr
+            .get_value()
+            .and_then(|val| val.get::<vt::Array<Token>>())
+            .map(|order| {
+                order
+                    .iter()
+                    .filter_map(|tok| parse_xform_op(tok.as_str()).ok())
+                    .map(|op| op.attr_name)
+                    .collect()
+            })
+            .unwrap_or_default()
+    } else {
+        vec!["xformOp:transform".to_string()]
+    };
+
+    names.iter().any(|name| {
+        let attr = prim.attribute(&Token::new(name.as_str()));
+        attr.is_valid() && attr.time_samples().len() > 1
+    })
+}
+
+/// World transform while walking the stage.
+#[derive(Clone)]
+struct WorldTransform {
+    /// Pose at the time the scene is evaluated at.
+    current: Matrix4d,
+    /// Pose at every frame of [`SceneBuilder::frames`] when animated.
+    animation: Option<Rc<Animation>>,
+}
+
+/// Poses of an animated prim, shared with its non-transforming descendants.
+struct Animation {
+    frames: Vec<Matrix4d>,
+    /// Transform track emitted for these poses, set by the first instance.
+    track: Cell<Option<usize>>,
+}
+
+impl Animation {
+    fn new(frames: Vec<Matrix4d>) -> Rc<Self> {
+        Rc::new(Self {
+            frames,
+            track: Cell::new(None),
+        })
+    }
+}
+
+impl WorldTransform {
+    fn identity() -> Self {
+        Self {
+            current: Matrix4d::identity(),
+            animation: None,
+        }
+    }
+
+    /// `self * xf` for a transform that does not change over time.
+    fn post_mult_static(&self, xf: &Matrix4d) -> Self {
+        Self {
+            current: self.current.post_mult(xf),
+            animation: self.animation.as_ref().map(|parent| {
+                Animation::new(parent.frames.iter().map(|m| m.post_mult(xf)).collect())
+            }),
+        }
+    }
+
+    /// `self * child`, animated when either side is.
+    fn post_mult(&self, child: &WorldTransform) -> Self {
+        let Some(child_frames) = &child.animation else {
+            return self.post_mult_static(&child.current);
+        };
+        let frames = match &self.animation {
+            Some(parent) => parent
+                .frames
+                .iter()
+                .zip(&child_frames.frames)
+                .map(|(p, c)| p.post_mult(c))
+                .collect(),
+            None => child_frames
+                .frames
+                .iter()
+                .map(|c| self.current.post_mult(c))
+                .collect(),
+        };
+        Self {
+            current: self.current.post_mult(&child.current),
+            animation: Some(Animation::new(frames)),
+        }
+    }
+}
+
+// -------- Mesh data --------
+fn malformed(prim: &usd::Prim, reason: impl Into<String>) -> UsdLoadError {
+    UsdLoadError::MalformedTopology {
+        path: prim.path().to_string(),
+        reason: reason.into(),
+    }
+}
+
+fn get_mesh_data(prim: &usd::Prim, time: TimeCode) -> Result<MeshData, UsdLoadError> {
+    let path = prim.path().clone();
+    let stage = prim.stage();
+    let mesh = usd_geom::Mesh::define(&stage, path);
+
+    // --- positions
+    let points_attr = mesh.points_attr();
+    if !points_attr.is_valid() {
+        return Err(malformed(prim, "missing points attribute"));
+    }
+    let positions: Vec<[f32; 3]> = attr_value(&points_attr, time)
+        .and_then(|val| val.get::<vt::Array<gf::Vec3f>>())
+        .ok_or_else(|| malformed(prim, "points is not a point3f[] array"))?
+        .iter()
+        .map(|p| [p.x, p.y, p.z])
+        .collect();
+
+    // --- faceVertexCounts
+    let fvc_attr = mesh.face_vertex_counts_attr();
+    if !fvc_attr.is_valid() {
+        return Err(malformed(prim, "missing faceVertexCounts attribute"));
+    }
+    let face_vertex_counts = attr_value(&fvc_attr, time)
+        .and_then(|val| val.get::<vt::Array<i32>>())
+        .ok_or_else(|| malformed(prim, "faceVertexCounts is not an int[] array"))?
+        .iter()
+        .map(|&c| {
+            usize::try_from(c).map_err(|_| malformed(prim, format!("negative face count {c}")))
+        })
+        .collect::<Result<Vec<usize>, _>>()?;
+
+    // --- faceVertexIndices
+    let fvi_attr = mesh.face_vertex_indices_attr();
+    if !fvi_attr.is_valid() {
+        return Err(malformed(prim, "missing faceVertexIndices attribute"));
+    }
+    let face_vertex_indices = attr_value(&fvi_attr, time)
+        .and_then(|val| val.get::<vt::Array<i32>>())
+        .ok_or_else(|| malformed(prim, "faceVertexIndices is not an int[] array"))?
+        .iter()
+        .map(|&i| {
+            usize::try_from(i).map_err(|_| malformed(prim, format!("negative vertex index {i}")))
+        })
+        .collect::<Result<Vec<usize>, _>>()?;
+
+    let face_count = face_vertex_counts.len();
+    let sum_counts: usize = face_vertex_counts.iter().sum();
+    if sum_counts != face_vertex_indices.len() {
+        return Err(malformed(
+            prim,
+            format!(
+                "sum(faceVertexCounts) = {} but faceVertexIndices has {} entries",
+                sum_counts,
+                face_vertex_indices.len()
+            ),
+        ));
+    }
+    if let Some(bad) = face_vertex_indices
+        .iter()
+        .position(|&i| i >= positions.len())
+    {
+        return Err(malformed(
+            prim,
+            format!(
+                "faceVertexIndices[{}] = {} out of range ({} points)",
+                bad,
+                face_vertex_indices[bad],
+                positions.len()
+            ),
+        ));
+    }
+
+    let mut primvars = read_primvars(prim, time);
+
+    // the normals attribute wins over primvars:normals
+    let normals_attr = mesh.normals_attr();
+    if normals_attr.is_valid() {
+        if let Some(normals) = read_primvar(
+            prim,
+            &normals_attr,
+            "normals:indices",
+            PrimvarInterpolation::Vertex,
+            time,
+        ) {
+            primvars.insert("normals".to_string(), normals);
+        }
+    }
+
+    Ok(MeshData {
+        positions,
+        face_vertex_counts,
+        face_vertex_indices,
+        primvars,
+        double_sided: read_double_sided(prim),
+        material_index: None,
+        subdivision: read_subdivision(prim, time, positions.len())?,
+        hole_indices: read_hole_indices(prim, time, face_count)?,
+        orientation: read_orientation(prim),
+    })
+}
+
+/// Subdivision scheme and sharpness tags, with the UsdGeomMesh fallbacks.
+fn read_subdivision(
+    prim: &usd::Prim,
+    time: TimeCode,
+    point_count: usize,
+) -> Result<SubdivisionData, UsdLoadError> {
+    let value = |name: &str| {
+        let attr = prim.attribute(&Token::new(name));
+        attr.is_valid().then(|| attr_value(&attr, time)).flatten()
+    };
+    let token = |name: &str, fallback: &str| {
+        value(name)
+            .and_then(|val| val.get::<Token>())
+            .map_or(fallback.to_string(), |tok| tok.as_str().to_string())
+    };
+    let points = |name: &str| -> Result<Vec<usize>, UsdLoadError> {
+        let Some(indices) = value(name).and_then(|val| val.get::<vt::Array<i32>>()) else {
+            return Ok(Vec::new());
+        };
+        indices
+            .iter()
+            .map(|&i| {
+                usize::try_from(i)
+                    .ok()
+                    .filter(|&i| i < point_count)
+                    .ok_or_else(|| malformed(prim, format!("{name} has bad point {i}")))
+            })
+            .collect()
+    };
+    let floats = |name: &str| -> Vec<f32> {
+        value(name)
+            .and_then(|val| val.get::<vt::Array<f32>>())
+            .map(|values| values.iter().copied().collect())
+            .unwrap_or_default()
+    };
+
+    let scheme = token("subdivisionScheme", "catmullClark");
+    let scheme = SubdivisionScheme::from_token(&scheme)
+        .ok_or_else(|| malformed(prim, format!("unknown subdivisionScheme {scheme}")))?;
+    let boundary = token("interpolateBoundary", "edgeAndCorner");
+    let interpolate_boundary = InterpolateBoundary::from_token(&boundary)
+        .ok_or_else(|| malformed(prim, format!("unknown interpolateBoundary {boundary}")))?;
+
+    // creases are chains of points, sharpness is given per chain or per edge
+    let crease_indices = points("creaseIndices")?;
+    let crease_lengths: Vec<usize> = value("creaseLengths")
+        .and_then(|val| val.get::<vt::Array<i32>>())
+        .map(|lengths| lengths.iter().map(|&n| n.max(0) as usize).collect())
+        .unwrap_or_default();
+    let crease_sharpnesses = floats("creaseSharpnesses");
+    if crease_lengths.iter().sum::<usize>() != crease_indices.len() {
+        return Err(malformed(
+            prim,
+            "creaseLengths do not add up to creaseIndices",
+        ));
+    }
+    let edge_count: usize = crease_lengths.iter().map(|n| n.saturating_sub(1)).sum();
+    let per_edge =
+        crease_sharpnesses.len() == edge_count && crease_sharpnesses.len() != crease_lengths.len();
+    if !per_edge && crease_sharpnesses.len() != crease_lengths.len() {
+        return Err(malformed(
+            prim,
+            "creaseSharpnesses do not match the creases",
+        ));
+    }
+    let mut crease_edges = Vec::with_capacity(edge_count);
+    let mut start = 0;
+    for (crease, &length) in crease_lengths.iter().enumerate() {
+        let chain = &crease_indices[start..start + length];
+        for pair in chain.windows(2) {
+            let sharpness = if per_edge {
+                crease_sharpnesses[crease_edges.len()]
+            } else {
+                crease_sharpnesses[crease]
+            };
+            crease_edges.push(([pair[0], pair[1]], sharpness));
+        }
+        start += length;
+    }
+
+    let corner_indices = points("cornerIndices")?;
+    let corner_sharpnesses = floats("cornerSharpnesses");
+    if corner_indices.len() != corner_sharpnesses.len() {
+        return Err(malformed(
+            prim,
+            "cornerSharpnesses do not match cornerIndices",
+        ));
+    }
+
+    Ok(SubdivisionData {
+        scheme,
+        interpolate_boundary,
+        crease_edges,
+        corners: corner_indices.into_iter().zip(corner_sharpnesses).collect(),
+    })
+}
+
+/// `holeIndices`, sorted and without duplicates.
+fn read_hole_indices(
+    prim: &usd::Prim,
+    time: TimeCode,
+    face_count: usize,
+) -> Result<Vec<usize>, UsdLoadError> {
+    let attr = prim.attribute(&Token::new("holeIndices"));
+    let Some(indices) = attr
+        .is_valid()
+        .then(|| attr_value(&attr, time))
+        .flatten()
+        .and_then(|val| val.get::<vt::Array<i32>>())
+    else {
+        return Ok(Vec::new());
+    };
+    let mut holes = indices
+        .iter()
+        .map(|&i| {
+            usize::try_from(i)
+                .ok()
+                .filter(|&i| i < face_count)
+                .ok_or_else(|| malformed(prim, format!("hole index {i} out of range")))
+        })
+        .collect::<Result<Vec<usize>, _>>()?;
+    holes.sort_unstable();
+    holes.dedup();
+    Ok(holes)
+}
+
+/// `orientation` is uniform, so it is read without a time code.
+fn read_orientation(prim: &usd::Prim) -> Orientation {
+    let prop = prim.property(&Token::new("orientation"));
+    let left_handed = prop.is_valid()
+        && prop
+            .get_value()
+            .and_then(|val| val.get::<Token>())
+            .is_some_and(|tok| tok.as_str() == "leftHanded");
+    if left_handed {
+        Orientation::LeftHanded
+    } else {
+        Orientation::RightHanded
+    }
+}
+
+fn read_double_sided(prim: &usd::Prim) -> bool {
+    let prop = prim.property(&Token::new("doubleSided"));
+    prop.is_valid()
+        && prop
+            .get_value()
+            .and_then(|val| val.get::<bool>())
+            .unwrap_or(false)
+}
+
+/// Every `primvars:*` attribute of `prim`, keyed without the namespace.
+fn read_primvars(prim: &usd::Prim, time: TimeCode) -> HashMap<String, PrimvarData> {
+    let mut primvars = HashMap::new();
+    for name in prim.property_names() {
+        let name = name.as_str();
+        let Some(primvar_name) = name.strip_prefix("primvars:") else {
+            continue;
+        };
+        if primvar_name.ends_with(":indices") {
+            continue;
+        }
+        // normals are per point unless stated otherwise
+        let fallback = if primvar_name == "normals" {
+            PrimvarInterpolation::Vertex
+        } else {
+            PrimvarInterpolation::Constant
+        };
+        let attr = prim.attribute(&Token::new(name));
+        if let Some(primvar) = read_primvar(prim, &attr, &format!("{name}:indices"), fallback, time)
+        {
+            primvars.insert(primvar_name.to_string(), primvar);
+        }
+    }
+    primvars
+}
+
+// -------- Curves and points --------
+fn read_points(prim: &usd::Prim, time: TimeCode) -> Result<Vec<[f32; 3]>, UsdLoadError> {
+    let attr = prim.attribute(&Token::new("points"));
+    if !attr.is_valid() {
+        return Err(malformed(prim, "missing points attribute"));
+    }
+    Ok(attr_value(&attr, time)
+        .and_then(|val| val.get::<vt::Array<gf::Vec3f>>())
+        .ok_or_else(|| malformed(prim, "points is not a point3f[] array"))?
+        .iter()
+        .map(|p| [p.x, p.y, p.z])
+        .collect())
+}
+
+/// `widths` or `normals`, which carry primvar metadata without the namespace.
+fn read_geometry_primvar(prim: &usd::Prim, name: &str, time: TimeCode) -> Option<PrimvarData> {
+    let attr = prim.attribute(&Token::new(name));
+    read_primvar(
+        prim,
+        &attr,
+        &format!("{name}:indices"),
+        PrimvarInterpolation::Vertex,
+        time,
+    )
+}
+
+fn get_curves_data(
+    prim: &usd::Prim,
+    time: TimeCode,
+    transform: InstanceTransform,
+) -> Result<CurvesData, UsdLoadError> {
+    let points = read_points(prim, time)?;
+    let counts_attr = prim.attribute(&Token::new("curveVertexCounts"));
+    if !counts_attr.is_valid() {
+        return Err(malformed(prim, "missing curveVertexCounts attribute"));
+    }
+    let curve_vertex_counts = attr_value(&counts_attr, time)
+        .and_then(|val| val.get::<vt::Array<i32>>())
+        .ok_or_else(|| malformed(prim, "curveVertexCounts is not an int[] array"))?
+        .iter()
+        .map(|&c| {
+            usize::try_from(c).map_err(|_| malformed(prim, format!("negative curve count {c}")))
+        })
+        .collect::<Result<Vec<usize>, _>>()?;
+    let total: usize = curve_vertex_counts.iter().sum();
+    if total != points.len() {
+        return Err(malformed(
+            prim,
+            format!(
+                "sum(curveVertexCounts) = {total} but there are {} points",
+                points.len()
+            ),
+        ));
+    }
+
+    // fallbacks from the UsdGeomBasisCurves schema
+    let token = |name: &str, fallback: &str| {
+        let attr = prim.attribute(&Token::new(name));
+        attr.is_valid()
+            .then(|| attr_value(&attr, time))
+            .flatten()
+            .and_then(|val| val.get::<Token>())
+            .map_or(fallback.to_string(), |tok| tok.as_str().to_string())
+    };
+    let (curve_type, basis) = (token("type", "cubic"), token("basis", "bezier"));
+    let basis = CurveBasis::from_tokens(&curve_type, &basis)
+        .ok_or_else(|| malformed(prim, format!("unsupported {curve_type} {basis} curves")))?;
+    let wrap = token("wrap", "nonperiodic");
+    let wrap = CurveWrap::from_token(&wrap)
+        .ok_or_else(|| malformed(prim, format!("unknown wrap {wrap}")))?;
+
+    let mut primvars = read_primvars(prim, time);
+    primvars.remove("normals");
+    Ok(CurvesData {
+        path: prim.path().to_string(),
+        points,
+        curve_vertex_counts,
+        basis,
+        wrap,
+        widths: read_geometry_primvar(prim, "widths", time).and_then(|w| w.as_float().cloned()),
+        normals: read_geometry_primvar(prim, "normals", time).and_then(|n| n.as_float3().cloned()),
+        primvars,
+        material_index: None,
+        transform,
+    })
+}
+
+fn get_points_data(
+    prim: &usd::Prim,
+    time: TimeCode,
+    transform: InstanceTransform,
+) -> Result<PointsData, UsdLoadError> {
+    Ok(PointsData {
+        path: prim.path().to_string(),
+        positions: read_points(prim, time)?,
+        widths: read_geometry_primvar(prim, "widths", time).and_then(|w| w.as_float().cloned()),
+        primvars: read_primvars(prim, time),
+        material_index: None,
+        transform,
+    })
+}
+
+// -------- Implicit surfaces --------
+/// `UsdGeom` implicit surfaces, tessellated by [`gprims`].
+const GPRIM_TYPES: &[&str] = &["Cube", "Sphere", "Cylinder", "Cone", "Capsule", "Plane"];
+
+/// Point a uniform primvar authored for faces in another order at the
+/// generated faces: face `i` takes the element authored for face `order[i]`.
+fn reorder_faces(primvar: &mut PrimvarData, order: &[usize]) -> Result<(), String> {
+    fn reorder<T>(primvar: &mut Primvar<T>, order: &[usize]) -> Result<(), String> {
+        let slots = primvar
+            .indices
+            .as_ref()
+            .map_or(primvar.element_count(), Vec::len);
+        if slots != order.len() {
+            return Err(format!("{slots} values for {} faces", order.len()));
+        }
+        primvar.indices = Some(match &primvar.indices {
+            Some(indices) => order.iter().map(|&face| indices[face]).collect(),
+            None => order.to_vec(),
+        });
+        Ok(())
+    }
+
+    match primvar {
+        PrimvarData::Float(p) => reorder(p, order),
+        PrimvarData::Float2(p) => reorder(p, order),
+        PrimvarData::Float3(p) => reorder(p, order),
+        PrimvarData::Float4(p) => reorder(p, order),
+        PrimvarData::Int(p) => reorder(p, order),
+    }
+}
+
+/// Tessellate an implicit surface from its schema attributes. Primvars that
+/// cannot follow the generated topology are pushed to `dropped`.
+fn get_gprim_data(
+    prim: &usd::Prim,
+    time: TimeCode,
+    segments: u32,
+    dropped: &mut Vec<UsdLoadError>,
+) -> Result<MeshData, UsdLoadError> {
+    let invalid = |reason: String| UsdLoadError::InvalidPrim {
+        path: prim.path().to_string(),
+        reason,
+    };
+    let value = |name: &str| {
+        let attr = prim.attribute(&Token::new(name));
+        if attr.is_valid() {
+            attr_value(&attr, time)
+        } else {
+            None
+        }
+    };
+    // fallbacks from the UsdGeom schemas
+    let length = |name: &str, fallback: f64| {
+        let length = value(name)
+            .and_then(|val| val.get::<f64>())
+            .unwrap_or(fallback);
+        if length < 0.0 {
+            return Err(invalid(format!("{name} is negative")));
+        }
+        Ok(length as f32)
+    };
+    let axis = match value("axis").and_then(|val| val.get::<Token>()) {
+        Some(token) => Axis::from_token(token.as_str())
+            .ok_or_else(|| invalid(format!("unknown axis {}", token.as_str())))?,
+        None => Axis::Z,
+    };
+
+    let type_name = prim.type_name();
+    let mut mesh = match type_name.as_str() {
+        "Cube" => gprims::cube(length("size", 2.0)?),
+        "Sphere" => gprims::sphere(length("radius", 1.0)?, segments),
+        "Cylinder" => gprims::cylinder(
+            length("radius", 1.0)?,
+            length("height", 2.0)?,
+            axis,
+            segments,
+        ),
+        "Cone" => gprims::cone(
+            length("radius", 1.0)?,
+            length("height", 2.0)?,
+            axis,
+            segments,
+        ),
+        "Capsule" => gprims::capsule(
+            length("radius", 0.5)?,
+            length("height", 1.0)?,
+            axis,
+            segments,
+        ),
+        "Plane" => gprims::plane(length("width", 2.0)?, length("length", 2.0)?, axis),
+        other => {
+            return Err(UsdLoadError::UnsupportedType {
+                path: prim.path().to_string(),
+                type_name: other.to_string(),
+            })
+        }
+    };
+
+    // the loader generates the surface itself, so only constant primvars and
+    // uniform ones on a Cube, whose face order USD defines, fit onto it
+    for (name, mut primvar) in read_primvars(prim, time) {
+        if name == "normals" {
+            continue;
+        }
+        let mapped = match primvar.interpolation() {
+            PrimvarInterpolation::Constant => Ok(()),
+            PrimvarInterpolation::Uniform if type_name.as_str() == "Cube" => {
+                reorder_faces(&mut primvar, &gprims::CUBE_USD_FACES)
+            }
+            interpolation => Err(format!(
+                "{interpolation:?} interpolation does not fit a generated {}",
+                type_name.as_str()
+            )),
+        };
+        match mapped {
+            Ok(()) => {
+                mesh.primvars.insert(name, primvar);
+            }
+            Err(reason) => dropped.push(UsdLoadError::DroppedPrimvar {
+                path: prim.path().to_string(),
+                name,
+                reason,
+            }),
+        }
+    }
+    mesh.double_sided = read_double_sided(prim);
+    Ok(mesh)
+}
+
+/// Read a primvar attribute with its interpolation, element size and indices.
+fn read_primvar(
+    prim: &usd::Prim,
+    attr: &usd::Attribute,
+    indices_name: &str,
+    fallback_interpolation: PrimvarInterpolation,
+    time: TimeCode,
+) -> Option<PrimvarData> {
+    if !attr.is_valid() {
+        return None;
+    }
+    let value = attr_value(attr, time)?;
+
+    let interpolation = attr
+        .metadata::<Token>(&Token::new("interpolation"))
+        .map_or(fallback_interpolation, |token| {
+            PrimvarInterpolation::from_token(token.as_str())
+        });
+    let element_size = attr
+        .metadata::<i32>(&Token::new("elementSize"))
+        .and_then(|size| usize::try_from(size).ok())
+        .filter(|&size| size > 0)
+        .unwrap_or(1);
+
+    let idx_attr = prim.attribute(&Token::new(indices_name));
+    let indices = if idx_attr.is_valid() {
+        attr_value(&idx_attr, time)
+            .and_then(|value| value.get::<vt::Array<i32>>())
+            .filter(|idx_arr| !idx_arr.is_empty())
+            .map(|idx_arr| idx_arr.iter().map(|&i| i as usize).collect())
+    } else {
+        None
+    };
+
+    let shell = Primvar::<()> {
+        values: Vec::new(),
+        indices,
+        interpolation,
+        element_size,
+        type_name: attr.type_name().as_str().to_string(),
+    };
+    let data = if let Some(arr) = value.get::<vt::Array<f32>>() {
+        PrimvarData::Float(shell.with_values(arr.iter().copied().collect()))
+    } else if let Some(arr) = value.get::<vt::Array<f64>>() {
+        PrimvarData::Float(shell.with_values(arr.iter().map(|&v| v as f32).collect()))
+    } else if let Some(arr) = value.get::<vt::Array<gf::Vec2f>>() {
+        PrimvarData::Float2(shell.with_values(arr.iter().map(|v| [v.x, v.y]).collect()))
+    } else if let Some(arr) = value.get::<vt::Array<gf::Vec3f>>() {
+        PrimvarData::Float3(shell.with_values(arr.iter().map(|v| [v.x, v.y, v.z]).collect()))
+    } else if let Some(arr) = value.get::<vt::Array<gf::Vec4f>>() {
+        PrimvarData::Float4(shell.with_values(arr.iter().map(|v| [v.x, v.y, v.z, v.w]).collect()))
+    } else if let Some(arr) = value.get::<vt::Array<i32>>() {
+        PrimvarData::Int(shell.with_values(arr.iter().copied().collect()))
+    } else {
+        return None;
+    };
+
+    (data.element_count() > 0).then_some(data)
+}
+
+// -------- Materials --------
+const STRONGER_THAN_DESCENDANTS: &str = "strongerThanDescendants";
+
+/// Binding purposes in the order they are tried; the viewer is a preview renderer.
+const BINDING_PURPOSES: [&str; 2] = ["preview", ""];
+
+fn prim_at(stage: &usd::Stage, path: &str) -> Option<usd::Prim> {
+    let prim = stage.prim_at_path(sdf::Path::new(path));
+    prim.is_valid().then_some(prim)
+}
+
+fn rel_targets(prim: &usd::Prim, name: &str) -> Vec<String> {
+    let rel = prim.relationship(&Token::new(name));
+    if rel.is_valid() {
+        rel.targets().iter().map(|path| path.to_string()).collect()
+    } else {
+        Vec::new()
+    }
+}
+
+fn is_stronger_than_descendants(prim: &usd::Prim, rel_name: &str) -> bool {
+    prim.relationship(&Token::new(rel_name))
+        .metadata::<Token>(&Token::new("bindMaterialAs"))
+        .is_some_and(|tok| tok.as_str() == STRONGER_THAN_DESCENDANTS)
+}
+
+/// Prims selected by a `UsdCollectionAPI` instance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Collection {
+    includes: Vec<String>,
+    excludes: Vec<String>,
+    /// `false` for `explicitOnly`, which does not pull in descendants.
+    expand: bool,
+}
+
+impl Collection {
+    fn read(stage: &usd::Stage, collection_path: &str) -> Option<Self> {
+        let (prim_path, name) = collection_path.split_once(".collection:")?;
+        let prim = prim_at(stage, prim_path)?;
+        let expansion_rule =
+            prim.attribute(&Token::new(&format!("collection:{name}:expansionRule")));
+        let expand = !(expansion_rule.is_valid()
+            && expansion_rule
+                .get_value()
+                .and_then(|val| val.get::<Token>())
+                .is_some_and(|tok| tok.as_str() == "explicitOnly"));
+
+        // nested collections are not followed
+        let prim_targets = |rel: &str| {
+            rel_targets(&prim, &format!("collection:{name}:{rel}"))
+                .into_iter()
+                .filter(|path| !path.contains(".collection:"))
+                .collect()
+        };
+        Some(Self {
+            includes: prim_targets("includes"),
+            excludes: prim_targets("excludes"),
+            expand,
+        })
+    }
+
+    /// Depth of the most specific rule matching `path`, if any.
+    fn rule_depth(rules: &[String], path: &str, expand: bool) -> Option<usize> {
+        rules
+            .iter()
+            .filter(|rule| {
+                path == rule.as_str()
+                    || (expand
+                        && path
+                            .strip_prefix(rule.as_str())
+                            .is_some_and(|rest| rest.starts_with('/') || rule.as_str() == "/"))
+            })
+            .map(|rule| match rule.as_str() {
+                "/" => 0,
+                rule => rule.matches('/').count(),
+            })
+            .max()
+    }
+
+    fn contains(&self, path: &str) -> bool {
+        let included = Self::rule_depth(&self.includes, path, self.expand);
+        let excluded = Self::rule_depth(&self.excludes, path, true);
+        match (included, excluded) {
+            (Some(inc), Some(exc)) => inc > exc,
+            (Some(_), None) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MaterialBinding {
+    material: String,
+    stronger: bool,
+}
+
+#[derive(Debug, Clone)]
+struct CollectionBinding {
+    collection: Collection,
+    binding: MaterialBinding,
+}
+
+/// Bindings authored on one prim, per entry of [`BINDING_PURPOSES`].
+#[derive(Debug, Clone, Default)]
+struct BindingLevel {
+    direct: [Option<MaterialBinding>; 2],
+    collections: [Vec<CollectionBinding>; 2],
+}
+
+impl BindingLevel {
+    fn read(stage: &usd::Stage, prim: &usd::Prim) -> Option<Self> {
+        let names: Vec<String> = prim
+            .property_names()
+            .iter()
+            .map(|tok| tok.as_str().to_string())
+            .filter(|name| name.starts_with("material:binding"))
+            .collect();
+        if names.is_empty() {
+            return None;
+        }
+
+        let mut level = BindingLevel::default();
+        for (slot, purpose) in BINDING_PURPOSES.iter().enumerate() {
+            let base = if purpose.is_empty() {
+                "material:binding".to_string()
+            } else {
+                format!("material:binding:{purpose}")
+            };
+
+            if names.contains(&base) {
+                level.direct[slot] =
+                    rel_targets(prim, &base)
+                        .into_iter()
+                        .next()
+                        .map(|material| MaterialBinding {
+                            material,
+                            stronger: is_stronger_than_descendants(prim, &base),
+                        });
+            }
+
+            let collection_prefix = format!("{base}:collection:");
+            for name in names.iter().filter(|n| n.starts_with(&collection_prefix)) {
+                // targets are the collection followed by the material
+                let targets = rel_targets(prim, name);
+                let [collection_path, material] = targets.as_slice() else {
+                    continue;
+                };
+                if let Some(collection) = Collection::read(stage, collection_path) {
+                    level.collections[slot].push(CollectionBinding {
+                        collection,
+                        binding: MaterialBinding {
+                            material: material.clone(),
+                            stronger: is_stronger_than_descendants(prim, name),
+                        },
+                    });
+                }
+            }
+        }
+        Some(level)
+    }
+}
+
+/// Binding levels of a prim and its ancestors, innermost first.
+struct BindingScope {
+    level: BindingLevel,
+    parent: Option<Rc<BindingScope>>,
+}
+
+/// Material path bound to `prim_path`, following `UsdShadeMaterialBindingAPI` rules:
+/// collection bindings beat direct ones on the same prim, the nearest binding wins
+/// unless an ancestor's is `strongerThanDescendants`, and purpose-specific
+/// bindings are preferred over all-purpose ones.
+fn resolve_binding(scope: Option<&Rc<BindingScope>>, prim_path: &str) -> Option<String> {
+    resolve_binding_in(&[scope], prim_path)
+}
+
+/// [`resolve_binding`] for a prim inside a prototype, whose bindings are split
+/// into scopes innermost first, each ending where the next one begins.
+fn resolve_binding_in(scopes: &[Option<&Rc<BindingScope>>], prim_path: &str) -> Option<String> {
+    (0..BINDING_PURPOSES.len()).find_map(|slot| {
+        let mut winner: Option<&MaterialBinding> = None;
+        for &scope in scopes {
+            let mut node = scope;
+            while let Some(current) = node {
+                let level = &current.level;
+                let found = level.collections[slot]
+                    .iter()
+                    .find(|cb| cb.collection.contains(prim_path))
+                    .map(|cb| &cb.binding)
+                    .or(level.direct[slot].as_ref());
+                if let Some(binding) = found {
+                    if winner.is_none() || binding.stronger {
+                        winner = Some(binding);
+                    }
+                }
+                node = current.parent.as_ref();
+            }
+        }
+        winner.map(|binding| binding.material.clone())
+    })
+}
+
+/// `inner` followed by `outer`, copying the levels of `inner`.
+fn chain_scopes(
+    inner: Option<&Rc<BindingScope>>,
+    outer: Option<&Rc<BindingScope>>,
+) -> Option<Rc<BindingScope>> {
+    match inner {
+        Some(scope) => Some(Rc::new(BindingScope {
+            level: scope.level.clone(),
+            parent: chain_scopes(scope.parent.as_ref(), outer),
+        })),
+        None => outer.cloned(),
+    }
+}
+
+/// Attribute holding a shader input's value, following connections to
+/// material interface inputs.
+fn input_attribute(prim: &usd::Prim, name: &str) -> Option<usd::Attribute> {
+    let attr = prim.attribute(&Token::new(&format!("inputs:{name}")));
+    if !attr.is_valid() {
+        return None;
+    }
+
+    for source in attr.connections() {
+        let source = source.to_string();
+        let Some((source_prim, source_attr)) = source.split_once('.') else {
+            continue;
+        };
+        if source_attr.starts_with("outputs:") {
+            // driven by another shader, handled by the texture reader
+            return None;
+        }
+        let source_prim = prim_at(&prim.stage(), source_prim)?;
+        let source_attr = source_prim.attribute(&Token::new(source_attr));
+        if source_attr.is_valid() {
+            return Some(source_attr);
+        }
+    }
+    Some(attr)
+}
+
+/// Value of a shader input, following connections to material interface inputs.
+fn shader_input(prim: &usd::Prim, name: &str, time: TimeCode) -> Option<vt::Value> {
+    input_attribute(prim, name).and_then(|attr| attr_value(&attr, time))
+}
+
+/// The `UsdUVTexture` shader and output driving a shader input.
+fn connected_texture(
+    stage: &usd::Stage,
+    prim: &usd::Prim,
+    name: &str,
+) -> Option<(usd::Prim, TextureChannel)> {
+    let attr = prim.attribute(&Token::new(&format!("inputs:{name}")));
+    if !attr.is_valid() {
+        return None;
+    }
+
+    attr.connections().iter().find_map(|source| {
+        let source = source.to_string();
+        let (source_prim, source_attr) = source.split_once('.')?;
+        let channel = TextureChannel::from_output(source_attr)?;
+        let texture = prim_at(stage, source_prim)?;
+        (shader_id(&texture).as_deref() == Some("UsdUVTexture")).then_some((texture, channel))
+    })
+}
+
+/// Anchor a relative asset path to the directory of the layer that authored it.
+fn resolve_asset_path(layer_path: &str, asset: &str) -> String {
+    let is_absolute = asset.starts_with('/')
+        || asset.starts_with('\\')
+        || asset.contains("://")
+        || asset.as_bytes().get(1) == Some(&b':');
+    if is_absolute {
+        return asset.to_string();
+    }
+
+    let relative = asset.strip_prefix("./").unwrap_or(asset);
+    match Path::new(layer_path).parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => {
+            dir.join(relative).to_string_lossy().into_owned()
+        }
+        _ => relative.to_string(),
+    }
+}
+
+/// Path of the strongest layer with an opinion on `attr`.
+fn authoring_layer_path(stage: &usd::Stage, attr: &usd::Attribute) -> String {
+    match attr.property_stack().first() {
+        Some(spec) => spec.layer().real_path().to_string(),
+        None => stage.root_layer().real_path().to_string(),
+    }
+}
+
+fn get_texture_data(
+    stage: &usd::Stage,
+    texture: &usd::Prim,
+    channel: TextureChannel,
+    time: TimeCode,
+) -> Option<TextureData> {
+    let file_attr = input_attribute(texture, "file")?;
+    let asset = attr_value(&file_attr, time)?
+        .get::<sdf::AssetPath>()?
+        .asset_path()
+        .to_string();
+    if asset.is_empty() {
+        return None;
+    }
+
+    let token = |name: &str| {
+        shader_input(texture, name, time)
+            .and_then(|val| val.get::<Token>())
+            .map(|tok| tok.as_str().to_string())
+            .unwrap_or_default()
+    };
+    let vec4 = |name: &str, fallback: [f32; 4]| {
+        shader_input(texture, name, time)
+            .and_then(|val| val.get::<gf::Vec4f>())
+            .map_or(fallback, |v| [v.x, v.y, v.z, v.w])
+    };
+
+    Some(TextureData {
+        file: resolve_asset_path(&authoring_layer_path(stage, &file_attr), &asset),
+        channel,
+        wrap_s: TextureWrap::from_token(&token("wrapS")),
+        wrap_t: TextureWrap::from_token(&token("wrapT")),
+        color_space: SourceColorSpace::from_token(&token("sourceColorSpace")),
+        scale: vec4("scale", [1.0; 4]),
+        bias: vec4("bias", [0.0; 4]),
+        fallback: vec4("fallback", [0.0, 0.0, 0.0, 1.0]),
+    })
+}
+
+fn shader_id(prim: &usd::Prim) -> Option<String> {
+    let attr = prim.attribute(&Token::new("info:id"));
+    if !attr.is_valid() {
+        return None;
+    }
+    attr.get_value()
+        .and_then(|val| val.get::<Token>())
+        .map(|tok| tok.as_str().to_string())
+}
+
+/// The `UsdPreviewSurface` shader driving a material's surface output.
+fn surface_shader(stage: &usd::Stage, material: &usd::Prim) -> Option<usd::Prim> {
+    for output in ["outputs:surface", "outputs:preview:surface"] {
+        let attr = material.attribute(&Token::new(output));
+        if !attr.is_valid() {
+            continue;
+        }
+        let connected = attr
+            .connections()
+            .iter()
+            .filter_map(|source| {
+                let source = source.to_string();
+                let (prim_path, _) = source.split_once('.')?;
+                prim_at(stage, prim_path)
+            })
+            .find(|shader| shader_id(shader).as_deref() == Some("UsdPreviewSurface"));
+        if connected.is_some() {
+            return connected;
+        }
+    }
+
+    // unconnected materials still often carry a preview surface child
+    material
+        .children()
+        .into_iter()
+        .find(|child| shader_id(child).as_deref() == Some("UsdPreviewSurface"))
+}
+
+fn get_material_data(
+    stage: &usd::Stage,
+    material_path: &str,
+    time: TimeCode,
+) -> Result<MaterialData, UsdLoadError> {
+    let invalid = |reason: &str| UsdLoadError::InvalidPrim {
+        path: material_path.to_string(),
+        reason: reason.to_string(),
+    };
+    let material =
+        prim_at(stage, material_path).ok_or_else(|| invalid("material does not exist"))?;
+    let shader = surface_shader(stage, &material)
+        .ok_or_else(|| invalid("no UsdPreviewSurface shader found"))?;
+
+    let color = |name: &str, fallback: [f32; 3]| {
+        shader_input(&shader, name, time)
+            .and_then(|val| val.get::<gf::Vec3f>())
+            .map_or(fallback, |c| [c.x, c.y, c.z])
+    };
+    let scalar = |name: &str, fallback: f32| {
+        shader_input(&shader, name, time)
+            .and_then(|val| val.get::<f32>())
+            .unwrap_or(fallback)
+    };
+
+    let texture = |name: &str| {
+        connected_texture(stage, &shader, name)
+            .and_then(|(texture, channel)| get_texture_data(stage, &texture, channel, time))
+    };
+
+    let defaults = MaterialData::default();
+    Ok(MaterialData {
+        path: material_path.to_string(),
+        diffuse_color: color("diffuseColor", defaults.diffuse_color),
+        emissive_color: color("emissiveColor", defaults.emissive_color),
+        metallic: scalar("metallic", defaults.metallic),
+        roughness: scalar("roughness", defaults.roughness),
+        opacity: scalar("opacity", defaults.opacity),
+        opacity_threshold: scalar("opacityThreshold", defaults.opacity_threshold),
+        ior: scalar("ior", defaults.ior),
+        normal: color("normal", defaults.normal),
+        textures: MaterialTextures {
+            diffuse_color: texture("diffuseColor"),
+            emissive_color: texture("emissiveColor"),
+            metallic: texture("metallic"),
+            roughness: texture("roughness"),
+            opacity: texture("opacity"),
+            normal: texture("normal"),
+            occlusion: texture("occlusion"),
+        },
+    })
+}
+
+#[cfg_attr(not(test), allow(dead_code))]
+fn matrix4d_to_mat4(matrix: &Matrix4d) -> Mat4 {
+    let src = matrix.as_array();
+    let mut cols = [0.0f32; 16];
+    for col in 0..4 {
+        for row in 0..4 {
+            cols[col * 4 + row] = src[row][col] as f32;
+        }
+    }
+    Mat4::from_cols_array(&cols)
+}
+
+fn mat4_to_matrix4d(mat: Mat4) -> Matrix4d {
+    let cols = mat.to_cols_array();
+    let mut data = [[0.0f64; 4]; 4];
+    for col in 0..4 {
+        for row in 0..4 {
+            data[row][col] = cols[col * 4 + row] as f64;
+        }
+    }
+    Matrix4d::from_array(data)
+}
+
+fn matrix4d_rows(matrix: &Matrix4d) -> [[f64; 4]; 4] {
+    let src = matrix.as_array();
+    let mut out = [[0.0f64; 4]; 4];
+    for row in 0..4 {
+        for col in 0..4 {
+            out[row][col] = src[row][col];
+        }
+    }
+    out
+}
+
+fn dmat4_to_matrix4d(mat: DMat4) -> Matrix4d {
+    let cols = mat.to_cols_array_2d();
+    let mut data = [[0.0f64; 4]; 4];
+    for (col, values) in cols.iter().enumerate() {
+        for (row, &value) in values.iter().enumerate() {
+            data[row][col] = value;
+        }
+    }
+    Matrix4d::from_array(data)
+}
+
+fn matrix4d_to_f32_array(matrix: &Matrix4d) -> [[f32; 4]; 4] {
+    let src = matrix.as_array();
+    let mut out = [[0.0f32; 4]; 4];
+    for row in 0..4 {
+        for col in 0..4 {
+            out[row][col] = src[row][col] as f32;
+        }
+    }
+    out
+}
+
+fn make_trs_matrix(pos: [f32; 3], rot: [f32; 4], scale: [f32; 3]) -> Matrix4d {
+    let translation = Vec3::from_array(pos);
+    let mut rotation = Quat::from_xyzw(rot[0], rot[1], rot[2], rot[3]);
+    if rotation.length_squared() <= f32::EPSILON {
+        rotation = Quat::IDENTITY;
+    } else {
+        rotation = rotation.normalize();
+    }
+    let scale = Vec3::from_array(scale);
+
+    let mat = Mat4::from_scale_rotation_translation(scale, rotation, translation);
+    mat4_to_matrix4d(mat)
+}
+
+// -------- Cameras --------
+/// Lens settings of a Camera prim, `transform` is left for the caller.
+fn get_camera_data(
+    prim: &usd::Prim,
+    time: TimeCode,
+    transform: InstanceTransform,
+) -> Result<CameraData, UsdLoadError> {
+    let value = |name: &str| {
+        let attr = prim.attribute(&Token::new(name));
+        if attr.is_valid() {
+            attr_value(&attr, time)
+        } else {
+            None
+        }
+    };
+    let scalar = |name: &str, fallback: f32| {
+        value(name)
+            .and_then(|val| val.get::<f32>())
+            .unwrap_or(fallback)
+    };
+
+    // fallbacks from the UsdGeomCamera schema
+    let projection = match value("projection").and_then(|val| val.get::<Token>()) {
+        Some(token) if token.as_str() == "orthographic" => CameraProjection::Orthographic,
+        _ => CameraProjection::Perspective,
+    };
+    let clipping_range = value("clippingRange")
+        .and_then(|val| val.get::<gf::Vec2f>())
+        .map_or([1.0, 1_000_000.0], |range| [range.x, range.y]);
+    let camera = CameraData {
+        path: prim.path().to_string(),
+        projection,
+        focal_length: scalar("focalLength", 50.0),
+        horizontal_aperture: scalar("horizontalAperture", 20.955),
+        vertical_aperture: scalar("verticalAperture", 15.2908),
+        clipping_range,
+        focus_distance: scalar("focusDistance", 0.0),
+        transform,
+    };
+
+    let invalid = |reason: &str| UsdLoadError::InvalidPrim {
+        path: camera.path.clone(),
+        reason: reason.to_string(),
+    };
+    if camera.focal_length <= 0.0 {
+        return Err(invalid("focalLength must be positive"));
+    }
+    if camera.horizontal_aperture <= 0.0 || camera.vertical_aperture <= 0.0 {
+        return Err(invalid("aperture must be positive"));
+    }
+    if !(0.0 < camera.clipping_range[0] && camera.clipping_range[0] < camera.clipping_range[1]) {
+        return Err(invalid("clippingRange must satisfy 0 < near < far"));
+    }
+    Ok(camera)
+}
+
+// -------- Lights --------
+/// `UsdLux` prim types turned into [`LightData`].
+const LIGHT_TYPES: &[&str] = &[
+    "DistantLight",
+    "SphereLight",
+    "DiskLight",
+    "RectLight",
+    "DomeLight",
+];
+
+/// A light input, authored as `inputs:<name>` since USD 21.05 and bare before.
+fn light_input(prim: &usd::Prim, name: &str, time: TimeCode) -> Option<vt::Value> {
+    [format!("inputs:{name}"), name.to_string()]
+        .iter()
+        .map(|name| prim.attribute(&Token::new(name)))
+        .find(|attr| attr.is_valid())
+        .and_then(|attr| attr_value(&attr, time))
+}
+
+/// `intensity` fallback of a UsdLux light schema.
+fn light_intensity_fallback(type_name: &str) -> f32 {
+    match type_name {
+        "DistantLight" => 50_000.0,
+        _ => 1.0,
+    }
+}
+
+fn get_light_data(
+    stage: &usd::Stage,
+    prim: &usd::Prim,
+    time: TimeCode,
+    transform: InstanceTransform,
+) -> LightData {
+    let scalar = |name: &str, fallback: f32| {
+        light_input(prim, name, time)
+            .and_then(|val| val.get::<f32>())
+            .unwrap_or(fallback)
+    };
+    let enabled = |name: &str| {
+        light_input(prim, name, time)
+            .and_then(|val| val.get::<bool>())
+            .unwrap_or(false)
+    };
+
+    // fallbacks from the UsdLux schemas
+    let kind = match prim.type_name().as_str() {
+        "DistantLight" => LightKind::Distant {
+            angle: scalar("angle", 0.53),
+        },
+        "DiskLight" => LightKind::Disk {
+            radius: scalar("radius", 0.5),
+        },
+        "RectLight" => LightKind::Rect {
+            width: scalar("width", 1.0),
+            height: scalar("height", 1.0),
+        },
+        "DomeLight" => {
+            let attr = ["inputs:texture:file", "texture:file"]
+                .iter()
+                .map(|name| prim.attribute(&Token::new(name)))
+                .find(|attr| attr.is_valid());
+            let texture = attr.and_then(|attr| {
+                let asset = attr_value(&attr, time)?.get::<sdf::AssetPath>()?;
+                let asset = asset.asset_path();
+                (!asset.is_empty())
+                    .then(|| resolve_asset_path(&authoring_layer_path(stage, &attr), asset))
+            });
+            LightKind::Dome { texture }
+        }
+        _ => LightKind::Sphere {
+            radius: scalar("radius", 0.5),
+        },
+    };
+    let color = light_input(prim, "color", time)
+        .and_then(|val| val.get::<gf::Vec3f>())
+        .map_or([1.0; 3], |c| [c.x, c.y, c.z]);
+    let color_temperature =
+        enabled("enableColorTemperature").then(|| scalar("colorTemperature", 6500.0));
+    let cone = light_input(prim, "shaping:cone:angle", time)
+        .and_then(|val| val.get::<f32>())
+        .filter(|&angle| angle < 90.0)
+        .map(|angle| LightCone {
+            angle,
+            softness: scalar("shaping:cone:softness", 0.0).clamp(0.0, 1.0),
+        });
+
+    LightData {
+        path: prim.path().to_string(),
+        kind,
+        intensity: scalar(
+            "intensity",
+            light_intensity_fallback(prim.type_name().as_str()),
+        ),
+        exposure: scalar("exposure", 0.0),
+        color,
+        color_temperature,
+        cone,
+        transform,
+    }
+}
+
+// -------- Scene builder --------
+/// Prim types that describe geometry the loader cannot convert yet.
+const UNSUPPORTED_GEOMETRY_TYPES: &[&str] =
+    &["NurbsCurves", "HermiteCurves", "NurbsPatch", "TetMesh"];
+
+struct SceneBuilder {
+    data: SceneData,
+    /// Time every attribute is read at.
+    time: TimeCode,
+    purposes: Vec<Purpose>,
+    gprim_segments: u32,
+    /// Time codes animated transforms are sampled at, empty for static stages.
+    frames: Vec<f64>,
+    /// `None` marks a prim whose mesh failed to load, so it is reported once.
+    mesh_lookup: HashMap<String, Option<usize>>,
+    /// Same as `mesh_lookup`, keyed by Material prim path.
+    material_lookup: HashMap<String, Option<usize>>,
+    /// Meshes placed by each expanded prototype, keyed by prototype path and
+    /// the purpose inherited by it.
+    prototype_lookup: HashMap<(String, Option<Purpose>), Rc<Vec<PrototypeInstance>>>,
+    /// Collects instances instead of emitting them while a prototype is expanded.
+    capture: Option<Vec<PrototypeInstance>>,
+}
+
+/// A mesh placed by a prototype, relative to the instance prim.
+struct PrototypeInstance {
+    mesh_index: usize,
+    xf: WorldTransform,
+    /// Path of the mesh prim below the prototype.
+    path: String,
+    /// Bindings between the prototype and the mesh, ending where the bindings
+    /// around each instance take over.
+    bindings: Option<Rc<BindingScope>>,
+}
+
+/// State a prim inherits from its ancestors.
+#[derive(Clone)]
+struct Inherited {
+    xf: WorldTransform,
+    bindings: Option<Rc<BindingScope>>,
+    /// Purpose of the nearest ancestor that authored one, which decides the
+    /// purpose of its whole subtree.
+    purpose: Option<Purpose>,
+}
+
+impl Inherited {
+    fn root() -> Self {
+        Self {
+            xf: WorldTransform::identity(),
+            bindings: None,
+            purpose: None,
+        }
+    }
+}
+
+impl SceneBuilder {
+    fn new(options: &LoadOptions) -> Self {
+        Self {
+            data: SceneData {
+                time_code: options.time_code,
+                ..SceneData::default()
+            },
+            time: options.time_code,
+            purposes: options.purposes.clone(),
+            gprim_segments: options.gprim_segments,
+            frames: Vec::new(),
+            mesh_lookup: HashMap::new(),
+            material_lookup: HashMap::new(),
+            prototype_lookup: HashMap::new(),
+            capture: None,
+        }
+    }
+
+    /// Sample animated transforms at every whole time code of the stage range.
+    fn set_metadata(&mut self, metadata: StageMetadata) {
+        if let (Some(start), Some(end)) = (metadata.start_time_code, metadata.end_time_code) {
+            if end > start {
+                let count = (end - start).floor() as usize + 1;
+                self.frames = (0..count).map(|i| start + i as f64).collect();
+            }
+        }
+        self.data.metadata = metadata;
+    }
+
+    /// World transform of `prim` under `parent`.
+    fn world_transform(&mut self, prim: &usd::Prim, parent: &WorldTransform) -> WorldTransform {
+        let local = get_local_transform(prim, self.time).unwrap_or_else(|err| {
+            self.report(err);
+            None
+        });
+        let Some(local) = local else {
+            return parent.clone();
+        };
+
+        let resets = local.resets_xform_stack;
+        let parent = if resets {
+            WorldTransform::identity()
+        } else {
+            parent.clone()
+        };
+
+        if self.frames.is_empty() || !has_animated_xform(prim) {
+            return parent.post_mult_static(&local.matrix);
+        }
+
+        // sampling errors were already reported for the current time
+        let local_frames: Vec<Matrix4d> = self
+            .frames
+            .iter()
+            .map(|&t| match get_local_transform(prim, TimeCode::At(t)) {
+                Ok(Some(sample)) => sample.matrix,
+                _ => local.matrix,
+            })
+            .collect();
+        let frames = match &parent.animation {
+            Some(parent_frames) => parent_frames
+                .frames
+                .iter()
+                .zip(&local_frames)
+                .map(|(p, l)| p.post_mult(l))
+                .collect(),
+            None => local_frames
+                .iter()
+                .map(|l| parent.current.post_mult(l))
+                .collect(),
+        };
+
+        WorldTransform {
+            current: parent.current.post_mult(&local.matrix),
+            animation: Some(Animation::new(frames)),
+        }
+    }
+
+    fn instance_transform(&mut self, xf: &WorldTransform) -> InstanceTransform {
+        let Some(animation) = &xf.animation else {
+            return InstanceTransform::Static(matrix4d_to_f32_array(&xf.current));
+        };
+        if let Some(index) = animation.track.get() {
+            return InstanceTransform::Animated(index);
+        }
+
+        let index = self.data.transform_tracks.len();
+        self.data.transform_tracks.push(TransformTrack {
+            times: self.frames.clone(),
+            transforms: animation.frames.iter().map(matrix4d_to_f32_array).collect(),
+        });
+        animation.track.set(Some(index));
+        InstanceTransform::Animated(index)
+    }
+
+    fn get_or_insert_material(&mut self, stage: &usd::Stage, path: &str) -> Option<usize> {
+        if let Some(&idx) = self.material_lookup.get(path) {
+            return idx;
+        }
+
+        let index = match get_material_data(stage, path, self.time) {
+            Ok(material) => {
+                self.data.materials.push(material);
+                Some(self.data.materials.len() - 1)
+            }
+            Err(err) => {
+                self.report(err);
+                None
+            }
+        };
+        self.material_lookup.insert(path.to_string(), index);
+        index
+    }
+
+    fn get_or_insert_mesh(
+        &mut self,
+        stage: &usd::Stage,
+        prim: &usd::Prim,
+        bindings: Option<&Rc<BindingScope>>,
+    ) -> Option<usize> {
+        let key = prim.path().to_string();
+        if let Some(&idx) = self.mesh_lookup.get(&key) {
+            return idx;
+        }
+
+        let mesh_data = match prim.type_name().as_str() {
+            "Mesh" => get_mesh_data(prim, self.time),
+            _ => get_gprim_data(
+                prim,
+                self.time,
+                self.gprim_segments,
+                &mut self.data.diagnostics,
+            ),
+        };
+        let index = match mesh_data {
+            Ok(mut mesh_data) => {
+                mesh_data.material_index = resolve_binding(bindings, &key)
+                    .and_then(|material| self.get_or_insert_material(stage, &material));
+                self.data.meshes.push(mesh_data);
+                Some(self.data.meshes.len() - 1)
+            }
+            Err(err) => {
+                self.report(err);
+                None
+            }
+        };
+        self.mesh_lookup.insert(key, index);
+        index
+    }
+
+    /// Place the mesh prim at `path`, or record it while a prototype is expanded
+    /// so its material can be resolved for every instance.
+    fn push_instance(
+        &mut self,
+        mesh_index: usize,
+        xf: &WorldTransform,
+        path: String,
+        bindings: Option<&Rc<BindingScope>>,
+    ) {
+        if let Some(capture) = &mut self.capture {
+            capture.push(PrototypeInstance {
+                mesh_index,
+                xf: xf.clone(),
+                path,
+                bindings: bindings.cloned(),
+            });
+            return;
+        }
+        let material_index = self.data.meshes[mesh_index].material_index;
+        self.place_mesh(mesh_index, xf, material_index);
+    }
+
+    /// Place the meshes of a prototype expanded at `path`, once per transform
+    /// in `xfs`, with `bindings` being the scope around the expanding prim.
+    ///
+    /// Each mesh's material is resolved at its path below `path` through the
+    /// bindings inside the prototype and then `bindings`, so collections and
+    /// `strongerThanDescendants` bindings around the instance apply per mesh.
+    fn place_template(
+        &mut self,
+        stage: &usd::Stage,
+        template: &[PrototypeInstance],
+        prototype_path: &str,
+        path: &str,
+        xfs: &[WorldTransform],
+        bindings: Option<&Rc<BindingScope>>,
+    ) {
+        for placed in template {
+            let mesh_path = match placed.path.strip_prefix(prototype_path) {
+                Some(rest) => format!("{path}{rest}"),
+                None => placed.path.clone(),
+            };
+            if self.capture.is_some() {
+                // nested prototypes are resolved once the outer instance is known
+                let scope = chain_scopes(placed.bindings.as_ref(), bindings);
+                for xf in xfs {
+                    let xf = xf.post_mult(&placed.xf);
+                    self.push_instance(placed.mesh_index, &xf, mesh_path.clone(), scope.as_ref());
+                }
+                continue;
+            }
+
+            let material_index =
+                resolve_binding_in(&[placed.bindings.as_ref(), bindings], &mesh_path)
+                    .and_then(|material| self.get_or_insert_material(stage, &material));
+            for xf in xfs {
+                self.place_mesh(placed.mesh_index, &xf.post_mult(&placed.xf), material_index);
+            }
+        }
+    }
+
+    fn place_mesh(
+        &mut self,
+        mesh_index: usize,
+        xf: &WorldTransform,
+        material_index: Option<usize>,
+    ) {
+        let transform = self.instance_transform(xf);
+        self.data.instances.push(MeshInstance {
+            mesh_index,
+            transform,
+            material_index,
+        });
+    }
+
+    /// Curves inside prototypes are not instanced and are skipped.
+    fn push_curves(
+        &mut self,
+        stage: &usd::Stage,
+        prim: &usd::Prim,
+        bindings: Option<&Rc<BindingScope>>,
+        xf: &WorldTransform,
+    ) {
+        if self.capture.is_some() {
+            return;
+        }
+        let transform = self.instance_transform(xf);
+        match get_curves_data(prim, self.time, transform) {
+            Ok(mut curves) => {
+                curves.material_index = resolve_binding(bindings, &curves.path)
+                    .and_then(|material| self.get_or_insert_material(stage, &material));
+                self.data.curves.push(curves);
+            }
+            Err(err) => self.report(err),
+        }
+    }
+
+    /// Points inside prototypes are skipped like curves.
+    fn push_points(
+        &mut self,
+        stage: &usd::Stage,
+        prim: &usd::Prim,
+        bindings: Option<&Rc<BindingScope>>,
+        xf: &WorldTransform,
+    ) {
+        if self.capture.is_some() {
+            return;
+        }
+        let transform = self.instance_transform(xf);
+        match get_points_data(prim, self.time, transform) {
+            Ok(mut points) => {
+                points.material_index = resolve_binding(bindings, &points.path)
+                    .and_then(|material| self.get_or_insert_material(stage, &material));
+                self.data.points.push(points);
+            }
+            Err(err) => self.report(err),
+        }
+    }
+
+    /// Cameras inside prototypes are not instanced and are skipped.
+    fn push_camera(&mut self, prim: &usd::Prim, xf: &WorldTransform) {
+        if self.capture.is_some() {
+            return;
+        }
+        let transform = self.instance_transform(xf);
+        match get_camera_data(prim, self.time, transform) {
+            Ok(camera) => self.data.cameras.push(camera),
+            Err(err) => self.report(err),
+        }
+    }
+
+    /// Lights inside prototypes are skipped like cameras.
+    fn push_light(&mut self, stage: &usd::Stage, prim: &usd::Prim, xf: &WorldTransform) {
+        if self.capture.is_some() {
+            return;
+        }
+        let transform = self.instance_transform(xf);
+        let light = get_light_data(stage, prim, self.time, transform);
+        self.data.lights.push(light);
+    }
+
+    fn report(&mut self, err: UsdLoadError) {
+        self.data.diagnostics.push(err);
+    }
+
+    fn into_scene(self) -> SceneData {
+        self.data
+    }
+}
+
+// -------- Recursively expand prims --------
+fn is_active(prim: &usd::Prim) -> bool {
+    prim.metadata::<bool>(&Token::new("active")).unwrap_or(true)
+}
+
+/// Visibility inherits, so an invisible prim hides its whole subtree.
+fn is_invisible(prim: &usd::Prim, time: TimeCode) -> bool {
+    let attr = prim.attribute(&Token::new("visibility"));
+    attr.is_valid()
+        && attr_value(&attr, time)
+            .and_then(|val| val.get::<Token>())
+            .is_some_and(|tok| tok.as_str() == "invisible")
+}
+
+/// Purpose authored on `prim` itself.
+fn authored_purpose(prim: &usd::Prim) -> Option<Purpose> {
+    let attr = prim.attribute(&Token::new("purpose"));
+    if !attr.is_valid() {
+        return None;
+    }
+    attr.get_value()
+        .and_then(|val| val.get::<Token>())
+        .and_then(|tok| Purpose::from_token(tok.as_str()))
+}
+
+fn expand_prim(stage: &usd::Stage, prim: &usd::Prim, parent: &Inherited, scene: &mut SceneBuilder) {
+    if !is_active(prim) || is_invisible(prim, scene.time) {
+        return;
+    }
+    // a prim's own purpose only counts when no ancestor authored one
+    let purpose = parent.purpose.or_else(|| authored_purpose(prim));
+    if purpose.is_some_and(|purpose| !scene.purposes.contains(&purpose)) {
+        return;
+    }
+    // unauthored prims are default, but their descendants may still author one
+    let included = scene.purposes.contains(&purpose.unwrap_or_default());
+
+    let world_xf = scene.world_transform(prim, &parent.xf);
+    let bindings = match BindingLevel::read(stage, prim) {
+        Some(level) => Some(Rc::new(BindingScope {
+            level,
+            parent: parent.bindings.clone(),
+        })),
+        None => parent.bindings.clone(),
+    };
+
+    match prim.type_name().as_str() {
+        "Mesh" | "PointInstancer" | "BasisCurves" | "Points" if !included => {}
+        type_name if !included && GPRIM_TYPES.contains(&type_name) => {}
+        type_name if !included && UNSUPPORTED_GEOMETRY_TYPES.contains(&type_name) => {}
+        type_name if type_name == "Mesh" || GPRIM_TYPES.contains(&type_name) => {
+            if let Some(mesh_index) = scene.get_or_insert_mesh(stage, prim, bindings.as_ref()) {
+                let path = prim.path().to_string();
+                scene.push_instance(mesh_index, &world_xf, path, bindings.as_ref());
+            }
+        }
+        "PointInstancer" => {
+            let state = Inherited {
+                xf: world_xf,
+                bindings,
+                purpose,
+            };
+            expand_point_instancer(stage, prim, &state, scene);
+        }
+        "BasisCurves" => scene.push_curves(stage, prim, bindings.as_ref(), &world_xf),
+        "Points" => scene.push_points(stage, prim, bindings.as_ref(), &world_xf),
+        "Camera" => scene.push_camera(prim, &world_xf),
+        type_name if LIGHT_TYPES.contains(&type_name) => scene.push_light(stage, prim, &world_xf),
+        type_name if UNSUPPORTED_GEOMETRY_TYPES.contains(&type_name) => {
+            scene.report(UsdLoadError::UnsupportedType {
+                path: prim.path().to_string(),
+                type_name: type_name.to_string(),
+            });
+        }
+        _ => {
+            let state = Inherited {
+                xf: world_xf,
+                bindings,
+                purpose,
+            };
+            if prim.is_instance() {
+                let prototype = prim.prototype();
+                if prototype.is_valid() {
+                    expand_instance(stage, prim, &prototype, &state, scene);
+                    return;
+                }
+            }
+            for child in prim.children() {
+                expand_prim(stage, &child, &state, scene);
+            }
+        }
+    }
+}
+
+/// Meshes placed by the prims in `roots`, relative to the identity.
+///
+/// Each prototype is expanded once per purpose and shared from then on.
+fn prototype_template(
+    stage: &usd::Stage,
+    path: &str,
+    roots: &[usd::Prim],
+    purpose: Option<Purpose>,
+    scene: &mut SceneBuilder,
+) -> Rc<Vec<PrototypeInstance>> {
+    let key = (path.to_string(), purpose);
+    if let Some(template) = scene.prototype_lookup.get(&key) {
+        return template.clone();
+    }
+
+    let outer = scene.capture.replace(Vec::new());
+    let local = Inherited {
+        xf: WorldTransform::identity(),
+        bindings: None,
+        purpose,
+    };
+    for root in roots {
+        expand_prim(stage, root, &local, scene);
+    }
+    let placed = std::mem::replace(&mut scene.capture, outer).unwrap_or_default();
+    let template = Rc::new(placed);
+    scene.prototype_lookup.insert(key, template.clone());
+    template
+}
+
+/// Place the meshes of an instance prim's prototype, expanding the prototype
+/// only the first time it is seen so every instance shares its meshes.
+fn expand_instance(
+    stage: &usd::Stage,
+    instance: &usd::Prim,
+    prototype: &usd::Prim,
+    state: &Inherited,
+    scene: &mut SceneBuilder,
+) {
+    let prototype_path = prototype.path().to_string();
+    let template = prototype_template(
+        stage,
+        &prototype_path,
+        &prototype.children(),
+        state.purpose,
+        scene,
+    );
+    scene.place_template(
+        stage,
+        &template,
+        &prototype_path,
+        &instance.path().to_string(),
+        std::slice::from_ref(&state.xf),
+        state.bindings.as_ref(),
+    );
+}
+
+/// An `int64listop` such as `inactiveIds`, reduced to what a set of ids needs.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct IdListOp {
+    /// Replaces the ids of weaker opinions when set.
+    explicit: Option<Vec<i64>>,
+    deleted: Vec<i64>,
+    /// Added, prepended and appended items, as order does not matter here.
+    added: Vec<i64>,
+}
+
+impl IdListOp {
+    fn read(prim: &usd::Prim, name: &str) -> Option<Self> {
+        let name = Token::new(name);
+        let Some(op) = prim.metadata::<sdf::Int64ListOp>(&name) else {
+            // some writers flatten the list op into a plain array
+            let ids = prim.metadata::<vt::Array<i64>>(&name)?;
+            return Some(Self {
+                explicit: Some(ids.iter().copied().collect()),
+                ..Self::default()
+            });
+        };
+        if op.is_explicit() {
+            return Some(Self {
+                explicit: Some(op.explicit_items().to_vec()),
+                ..Self::default()
+            });
+        }
+        Some(Self {
+            explicit: None,
+            deleted: op.deleted_items().to_vec(),
+            added: op
+                .added_items()
+                .iter()
+                .chain(op.prepended_items())
+                .chain(op.appended_items())
+                .copied()
+                .collect(),
+        })
+    }
+
+    /// Apply the operations to `ids`, deleting before adding as USD does.
+    fn apply(&self, ids: &mut HashSet<i64>) {
+        if let Some(explicit) = &self.explicit {
+            ids.clear();
+            ids.extend(explicit);
+            return;
+        }
+        for id in &self.deleted {
+            ids.remove(id);
+        }
+        ids.extend(&self.added);
+    }
+}
+
+/// Ids hidden by `invisibleIds` or deactivated by `inactiveIds`.
+fn masked_ids(invisible: Vec<i64>, inactive: Option<&IdListOp>) -> HashSet<i64> {
+    let mut inactive_ids = HashSet::new();
+    if let Some(op) = inactive {
+        op.apply(&mut inactive_ids);
+    }
+    invisible.into_iter().chain(inactive_ids).collect()
+}
+
+/// Per-point arrays of a PointInstancer at the scene time.
+#[derive(Debug, Default)]
+struct InstancerPoints {
+    proto_indices: Vec<i32>,
+    ids: Option<Vec<i64>>,
+    positions: Vec<[f32; 3]>,
+    orientations: Vec<[f32; 4]>,
+    scales: Vec<[f32; 3]>,
+    velocities: Vec<[f32; 3]>,
+    /// Degrees per second.
+    angular_velocities: Vec<[f32; 3]>,
+    /// Seconds from the sample the arrays were authored at to the scene time.
+    velocity_seconds: f32,
+    /// `invisibleIds` and `inactiveIds` combined.
+    masked_ids: HashSet<i64>,
+}
+
+impl InstancerPoints {
+    fn read(
+        prim: &usd::Prim,
+        inst: &usd_geom::PointInstancer,
+        time: TimeCode,
+        time_codes_per_second: f64,
+    ) -> Self {
+        let vec3s = |attr: &usd::Attribute| -> Vec<[f32; 3]> {
+            attr_value(attr, time)
+                .and_then(|val| val.get::<vt::Array<gf::Vec3f>>())
+                .map(|arr| arr.iter().map(|p| [p.x, p.y, p.z]).collect())
+                .unwrap_or_default()
+        };
+        let ids = |attr: &usd::Attribute| -> Option<Vec<i64>> {
+            attr_value(attr, time)
+                .and_then(|val| val.get::<vt::Array<i64>>())
+                .map(|arr| arr.iter().copied().collect())
+        };
+
+        let orientations: Vec<[f32; 4]> = match attr_value(&inst.orientations_attr(), time) {
+            Some(val) => {
+                if let Some(arr) = val.get::<vt::Array<gf::Quatf>>() {
+                    arr.iter().map(|q| [q.i, q.j, q.k, q.w]).collect()
+                } else if let Some(arr) = val.get::<vt::Array<gf::Quatd>>() {
+                    arr.iter()
+                        .map(|q| [q.i as f32, q.j as f32, q.k as f32, q.w as f32])
+                        .collect()
+                } else if let Some(arr) = val.get::<vt::Array<gf::Quath>>() {
+                    arr.iter()
+                        .map(|q| [q.i.into(), q.j.into(), q.k.into(), q.w.into()])
+                        .collect()
+                } else {
+                    vec![]
+                }
+            }
+            None => vec![],
+        };
+
+        // velocities extrapolate from the positions sample at or before the scene time
+        let positions_attr = inst.positions_attr();
+        let velocity_seconds = match time {
+            TimeCode::At(t) if time_codes_per_second > 0.0 => positions_attr
+                .time_samples()
+                .into_iter()
+                .filter(|&sample| sample <= t)
+                .reduce(f64::max)
+                .map_or(0.0, |sample| ((t - sample) / time_codes_per_second) as f32),
+            _ => 0.0,
+        };
+
+        let masked_ids = masked_ids(
+            ids(&prim.attribute(&Token::new("invisibleIds"))).unwrap_or_default(),
+            IdListOp::read(prim, "inactiveIds").as_ref(),
+        );
+
+        Self {
+            proto_indices: attr_value(&inst.proto_indices_attr(), time)
+                .and_then(|val| val.get::<vt::Array<i32>>())
+                .map(|arr| arr.iter().copied().collect())
+                .unwrap_or_default(),
+            ids: ids(&prim.attribute(&Token::new("ids"))),
+            positions: vec3s(&positions_attr),
+            orientations,
+            scales: vec3s(&inst.scales_attr()),
+            velocities: vec3s(&prim.attribute(&Token::new("velocities"))),
+            angular_velocities: vec3s(&prim.attribute(&Token::new("angularVelocities"))),
+            velocity_seconds,
+            masked_ids,
+        }
+    }
+
+    /// Point indices per prototype in a single pass, leaving out masked points
+    /// and returning the first out of range `protoIndices` entry.
+    fn group_by_prototype(&self, prototype_count: usize) -> (Vec<Vec<usize>>, Option<i32>) {
+        let mut groups = vec![Vec::new(); prototype_count];
+        let mut out_of_range = None;
+        for (point, &proto) in self.proto_indices.iter().enumerate() {
+            let id = self
+                .ids
+                .as_ref()
+                .and_then(|ids| ids.get(point).copied())
+                .unwrap_or(point as i64);
+            if self.masked_ids.contains(&id) {
+                continue;
+            }
+            match usize::try_from(proto).ok().and_then(|i| groups.get_mut(i)) {
+                Some(group) => group.push(point),
+                None => {
+                    out_of_range.get_or_insert(proto);
+                }
+            }
+        }
+        (groups, out_of_range)
+    }
+
+    /// Instance transform of `point` relative to the PointInstancer.
+    fn transform(&self, point: usize) -> Matrix4d {
+        let dt = self.velocity_seconds;
+        let mut pos = *self.positions.get(point).unwrap_or(&[0.0, 0.0, 0.0]);
+        if let Some(v) = self.velocities.get(point) {
+            for (p, v) in pos.iter_mut().zip(v) {
+                *p += v * dt;
+            }
+        }
+
+        let mut rot = *self
+            .orientations
+            .get(point)
+            .unwrap_or(&[0.0, 0.0, 0.0, 1.0]);
+        if let Some(&w) = self.angular_velocities.get(point) {
+            if dt != 0.0 {
+                let spin = Quat::from_scaled_axis(Vec3::from(w) * dt.to_radians());
+                rot = (spin * Quat::from_array(rot)).to_array();
+            }
+        }
+
+        let scale = *self.scales.get(point).unwrap_or(&[1.0, 1.0, 1.0]);
+        make_trs_matrix(pos, rot, scale)
+    }
+}
+
+/// Place every point of a PointInstancer, expanding each prototype once.
+fn expand_point_instancer(
+    stage: &usd::Stage,
+    prim: &usd::Prim,
+    state: &Inherited,
+    scene: &mut SceneBuilder,
+) {
+    let inst = usd_geom::PointInstancer::define(stage, prim.path().clone());
+    let points = InstancerPoints::read(
+        prim,
+        &inst,
+        scene.time,
+        scene.data.metadata.time_codes_per_second,
+    );
+
+    let targets = inst.prototypes_rel().targets();
+    let (groups, out_of_range) = points.group_by_prototype(targets.len());
+    if let Some(bad) = out_of_range {
+        scene.report(UsdLoadError::InvalidPrim {
+            path: prim.path().to_string(),
+            reason: format!(
+                "protoIndices entry {} out of range ({} prototypes)",
+                bad,
+                targets.len()
+            ),
+        });
+    }
+
+    for (path, group) in targets.iter().zip(&groups) {
+        let proto = stage.prim_at_path(path.clone());
+        if !proto.is_valid() {
+            scene.report(UsdLoadError::InvalidPrim {
+                path: path.to_string(),
+                reason: format!("prototype of {} does not exist", prim.path()),
+            });
+            continue;
+        }
+        if group.is_empty() {
+            continue;
+        }
+
+        let proto_path = proto.path().to_string();
+        let template = prototype_template(
+            stage,
+            &proto_path,
+            std::slice::from_ref(&proto),
+            state.purpose,
+            scene,
+        );
+        let point_xfs: Vec<WorldTransform> = group
+            .iter()
+            .map(|&point| state.xf.post_mult_static(&points.transform(point)))
+            .collect();
+        // prototypes are real prims below the instancer, so no paths are remapped
+        scene.place_template(
+            stage,
+            &template,
+            &proto_path,
+            &proto_path,
+            &point_xfs,
+            state.bindings.as_ref(),
+        );
+    }
+}
+
+// -------- Mesh deduplication --------
+fn hash_floats<'a>(values: impl IntoIterator<Item = &'a f32>, state: &mut impl Hasher) {
+    for value in values {
+        value.to_bits().hash(state);
+    }
+}
+
+fn hash_primvar<T>(primvar: &Primvar<T>, flat: &[f32], state: &mut impl Hasher) {
+    primvar.indices.hash(state);
+    (primvar.interpolation as u8).hash(state);
+    primvar.element_size.hash(state);
+    primvar.type_name.hash(state);
+    hash_floats(flat, state);
+}
+
+/// Hash of everything that ends up in the Bevy mesh.
+fn mesh_content_hash(mesh: &MeshData) -> u64 {
+    let mut state = DefaultHasher::new();
+    hash_floats(mesh.positions.iter().flatten(), &mut state);
+    mesh.face_vertex_counts.hash(&mut state);
+    mesh.face_vertex_indices.hash(&mut state);
+    mesh.double_sided.hash(&mut state);
+    mesh.material_index.hash(&mut state);
+    mesh.hole_indices.hash(&mut state);
+    mesh.orientation.hash(&mut state);
+    let subdivision = &mesh.subdivision;
+    (subdivision.scheme, subdivision.interpolate_boundary).hash(&mut state);
+    for (edge, sharpness) in &subdivision.crease_edges {
+        edge.hash(&mut state);
+        hash_floats([sharpness], &mut state);
+    }
+    for (corner, sharpness) in &subdivision.corners {
+        corner.hash(&mut state);
+        hash_floats([sharpness], &mut state);
+    }
+
+    let mut names: Vec<&String> = mesh.primvars.keys().collect();
+    names.sort();
+    for name in names {
+        name.hash(&mut state);
+        match &mesh.primvars[name] {
+            PrimvarData::Float(p) => hash_primvar(p, &p.values, &mut state),
+            PrimvarData::Float2(p) => hash_primvar(p, p.values.as_flattened(), &mut state),
+            PrimvarData::Float3(p) => hash_primvar(p, p.values.as_flattened(), &mut state),
+            PrimvarData::Float4(p) => hash_primvar(p, p.values.as_flattened(), &mut state),
+            PrimvarData::Int(p) => {
+                hash_primvar(p, &[], &mut state);
+                p.values.hash(&mut state);
+            }
+        }
+    }
+    state.finish()
+}
+
+/// Rough heap size of a mesh, used for the dedup report.
+fn mesh_bytes(mesh: &MeshData) -> usize {
+    fn primvar_bytes<T>(p: &Primvar<T>) -> usize {
+        p.values.len() * size_of::<T>()
+            + p.indices.as_ref().map_or(0, Vec::len) * size_of::<usize>()
+    }
+
+    let primvars: usize = mesh
+        .primvars
+        .values()
+        .map(|primvar| match primvar {
+            PrimvarData::Float(p) => primvar_bytes(p),
+            PrimvarData::Float2(p) => primvar_bytes(p),
+            PrimvarData::Float3(p) => primvar_bytes(p),
+            PrimvarData::Float4(p) => primvar_bytes(p),
+            PrimvarData::Int(p) => primvar_bytes(p),
+        })
+        .sum();
+    mesh.positions.len() * size_of::<[f32; 3]>()
+        + (mesh.face_vertex_counts.len() + mesh.face_vertex_indices.len()) * size_of::<usize>()
+        + primvars
+}
+
+/// Merge meshes with identical content and point their instances at the survivor.
+fn dedup_meshes(scene: &mut SceneData) -> DedupReport {
+    let meshes_before = scene.meshes.len();
+    let mut buckets: HashMap<u64, Vec<usize>> = HashMap::new();
+    let mut remap = Vec::with_capacity(meshes_before);
+    let mut kept: Vec<MeshData> = Vec::new();
+    let mut bytes_saved = 0;
+
+    for mesh in std::mem::take(&mut scene.meshes) {
+        // equal hashes are compared in full, collisions stay separate meshes
+        let bucket = buckets.entry(mesh_content_hash(&mesh)).or_default();
+        match bucket.iter().find(|&&index| kept[index] == mesh) {
+            Some(&index) => {
+                bytes_saved += mesh_bytes(&mesh);
+                remap.push(index);
+            }
+            None => {
+                bucket.push(kept.len());
+                remap.push(kept.len());
+                kept.push(mesh);
+            }
+        }
+    }
+
+    for instance in &mut scene.instances {
+        instance.mesh_index = remap[instance.mesh_index];
+    }
+    scene.meshes = kept;
+
+    DedupReport {
+        meshes_before,
+        meshes_after: scene.meshes.len(),
+        bytes_saved,
+    }
+}
+
+// -------- Entry point --------
+/// Open `stagep` through the backend's asset resolution, so anchored,
+/// resolver and packaged paths open wherever the backend can read them.
+fn open_stage(stagep: &str) -> Result<usd::Stage, UsdLoadError> {
+    usd::Stage::try_open(stagep).map_err(|err| UsdLoadError::StageOpen {
+        path: stagep.to_string(),
+        reason: err.to_string(),
+    })
+}
+
+fn load_stage(stagep: &str, options: &LoadOptions) -> Result<SceneData, UsdLoadError> {
+    let stage = open_stage(stagep)?;
+    let mut builder = SceneBuilder::new(options);
+    let metadata = read_stage_metadata(&stage);
+    let mut root = Inherited::root();
+    if options.correct_units_and_axis {
+        root.xf.current = dmat4_to_matrix4d(metadata.root_correction());
+    }
+    builder.set_metadata(metadata);
+
+    expand_prim(&stage, &stage.pseudo_root(), &root, &mut builder);
+
+    let mut scene = builder.into_scene();
+    if options.dedup_meshes {
+        scene.dedup = Some(dedup_meshes(&mut scene));
+    }
+    Ok(scene)
+}
+
+/// Load every mesh instance of the stage at `stagep` from default values.
+///
+/// Fails only when the stage itself cannot be opened. Prims that cannot be
+/// converted are skipped and listed in [`SceneData::diagnostics`], so an empty
+/// scene with no diagnostics really is an empty stage.
+pub fn fetch_stage_usd(stagep: &str) -> Result<SceneData, UsdLoadError> {
+    load_stage(stagep, &LoadOptions::default())
+}
+
+/// Like [`fetch_stage_usd`], but transforms, points, normals and
+/// PointInstancer arrays are evaluated at `time_code`.
+pub fn fetch_stage_usd_at(stagep: &str, time_code: f64) -> Result<SceneData, UsdLoadError> {
+    let options = LoadOptions {
+        time_code: TimeCode::At(time_code),
+        ..LoadOptions::default()
+    };
+    load_stage(stagep, &options)
+}
+
+/// Like [`fetch_stage_usd`] with explicit [`LoadOptions`].
+pub fn fetch_stage_usd_with(
+    stagep: &str,
+    options: &LoadOptions,
+) -> Result<SceneData, UsdLoadError> {
+    load_stage(stagep, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx_eq(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn make_trs_matches_glam() {
         let pos = [1.0, -2.5, 3.25];
         let rot = [0.1, 0.2, 0.3, 0.9];
         let scale = [2.0, 3.0, 0.5];
@@ -441,6 +3384,762 @@ mod tests {
         }
     }
 
+    #[test]
+    fn load_error_reports_path() {
+        let err = UsdLoadError::MalformedTopology {
+            path: "/World/Mesh".to_string(),
+            reason: "missing points attribute".to_string(),
+        };
+        assert_eq!(err.path(), "/World/Mesh");
+        assert_eq!(
+            err.to_string(),
+            "malformed topology on /World/Mesh: missing points attribute"
+        );
+    }
+
+    #[test]
+    fn fetch_missing_stage_is_an_error() {
+        let result = fetch_stage_usd("does/not/exist.usda");
+        assert!(matches!(result, Err(UsdLoadError::StageOpen { .. })));
+    }
+
+    #[test]
+    fn unparseable_stage_is_an_error() {
+        let path = std::env::temp_dir().join("bevytos_unparseable.usda");
+        std::fs::write(&path, "#usda 1.0\ndef Mesh \"Broken\" {").unwrap();
+        let result = fetch_stage_usd(path.to_str().unwrap());
+        let _ = std::fs::remove_file(&path);
+        assert!(matches!(result, Err(UsdLoadError::StageOpen { .. })));
+    }
+
+    #[test]
+    fn default_options_load_render_geometry() {
+        let options = LoadOptions::default();
+        assert_eq!(options.time_code, TimeCode::Default);
+        assert!(options.purposes.contains(&Purpose::Default));
+        assert!(options.purposes.contains(&Purpose::Render));
+        assert!(!options.purposes.contains(&Purpose::Proxy));
+        assert!(!options.purposes.contains(&Purpose::Guide));
+        assert_eq!(Purpose::from_token("guide"), Some(Purpose::Guide));
+        assert_eq!(Purpose::from_token("bogus"), None);
+    }
+
+    /// Load `text` as a `.usda` stage written to the temp directory.
+    fn load_usda(name: &str, text: &str, options: &LoadOptions) -> SceneData {
+        let path = std::env::temp_dir().join(format!("bevytos_{name}.usda"));
+        std::fs::write(&path, format!("#usda 1.0\n{text}")).unwrap();
+        let scene = fetch_stage_usd_with(path.to_str().unwrap(), options);
+        let _ = std::fs::remove_file(&path);
+        scene.unwrap()
+    }
+
+    /// A one triangle mesh starting at `x`, so loaded meshes can be told apart.
+    fn usda_triangle(name: &str, x: f32, metadata: &str, body: &str) -> String {
+        let x1 = x + 1.0;
+        let metadata = if metadata.is_empty() {
+            String::new()
+        } else {
+            format!(" ({metadata})")
+        };
+        format!(
+            "def Mesh \"{name}\"{metadata} {{\n\
+             int[] faceVertexCounts = [3]\n\
+             int[] faceVertexIndices = [0, 1, 2]\n\
+             point3f[] points = [({x}, 0, 0), ({x1}, 0, 0), ({x}, 1, 0)]\n\
+             {body}\n}}\n",
+        )
+    }
+
+    /// Start of every drawn mesh, in instance order.
+    fn drawn(scene: &SceneData) -> Vec<f32> {
+        scene
+            .instances
+            .iter()
+            .map(|instance| scene.meshes[instance.mesh_index].positions[0][0])
+            .collect()
+    }
+
+    #[test]
+    fn ancestor_purpose_decides_the_subtree() {
+        let stage = format!(
+            "def Xform \"Guide\" {{\n\
+             uniform token purpose = \"guide\"\n{}{}}}\n\
+             def Xform \"Plain\" {{\n{}{}}}\n",
+            usda_triangle("Render", 1.0, "", "uniform token purpose = \"render\""),
+            usda_triangle("Unauthored", 2.0, "", ""),
+            usda_triangle("Proxy", 3.0, "", "uniform token purpose = \"proxy\""),
+            usda_triangle("Render", 4.0, "", "uniform token purpose = \"render\""),
+        );
+        let scene = load_usda("purpose", &stage, &LoadOptions::default());
+        assert_eq!(drawn(&scene), vec![4.0]);
+
+        let guides = LoadOptions {
+            purposes: vec![Purpose::Guide],
+            ..LoadOptions::default()
+        };
+        let scene = load_usda("purpose_guides", &stage, &guides);
+        assert_eq!(drawn(&scene), vec![1.0, 2.0]);
+    }
+
+    #[test]
+    fn invisible_and_inactive_prims_hide_their_subtree() {
+        let stage = format!(
+            "def Xform \"Hidden\" {{\n\
+             token visibility = \"invisible\"\n{}}}\n\
+             def Xform \"Off\" (active = false) {{\n{}}}\n\
+             def Xform \"Shown\" {{\n{}{}}}\n",
+            usda_triangle("Visible", 1.0, "", "token visibility = \"inherited\""),
+            usda_triangle("Child", 2.0, "", ""),
+            usda_triangle("Visible", 3.0, "", ""),
+            usda_triangle("Off", 4.0, "active = false", ""),
+        );
+        let scene = load_usda("visibility", &stage, &LoadOptions::default());
+        assert_eq!(drawn(&scene), vec![3.0]);
+    }
+
+    fn assert_dmat4_eq(a: DMat4, b: DMat4) {
+        let diff = a - b;
+        for v in diff.to_cols_array() {
+            assert!(v.abs() < 1e-9, "matrix mismatch:\n{a}\n{b}");
+        }
+    }
+
+    fn op(entry: &str) -> XformOp {
+        parse_xform_op(entry).unwrap()
+    }
+
+    #[test]
+    fn parses_op_names() {
+        let pivot = op("!invert!xformOp:translate:pivot");
+        assert_eq!(pivot.op_type, XformOpType::Translate);
+        assert_eq!(pivot.attr_name, "xformOp:translate:pivot");
+        assert!(pivot.inverse);
+
+        assert_eq!(
+            op("xformOp:rotateZYX").op_type,
+            XformOpType::RotateEuler([EulerAxis::Z, EulerAxis::Y, EulerAxis::X])
+        );
+        assert!(parse_xform_op("xformOp:shear").is_err());
+        assert!(parse_xform_op("translate").is_err());
+    }
+
+    #[test]
+    fn reset_xform_stack_drops_earlier_ops() {
+        let order = parse_xform_op_order(["xformOp:translate", RESET_XFORM_STACK, "xformOp:scale"])
+            .unwrap();
+        assert!(order.resets_xform_stack);
+        assert_eq!(order.ops, vec![op("xformOp:scale")]);
+
+        let order = parse_xform_op_order(["xformOp:translate"]).unwrap();
+        assert!(!order.resets_xform_stack);
+    }
+
+    #[test]
+    fn translate_and_scale_ops() {
+        let m = compose_xform_ops(&[
+            (op("xformOp:translate"), XformOpValue::Vec3([1.0, 2.0, 3.0])),
+            (op("xformOp:scale"), XformOpValue::Vec3([2.0, 2.0, 2.0])),
+        ])
+        .unwrap();
+        // scale applies first, then the translation
+        let p = m.transform_point3(DVec3::new(1.0, 0.0, 0.0));
+        assert!((p - DVec3::new(3.0, 2.0, 3.0)).length() < 1e-9);
+    }
+
+    #[test]
+    fn single_axis_rotations() {
+        for (name, axis) in [
+            ("xformOp:rotateX", DVec3::X),
+            ("xformOp:rotateY", DVec3::Y),
+            ("xformOp:rotateZ", DVec3::Z),
+        ] {
+            let m = compose_xform_ops(&[(op(name), XformOpValue::Scalar(90.0))]).unwrap();
+            assert_dmat4_eq(m, DMat4::from_axis_angle(axis, std::f64::consts::FRAC_PI_2));
+        }
+    }
+
+    #[test]
+    fn euler_rotation_orders() {
+        let angles = [30.0, 45.0, 60.0];
+        let rx = DMat4::from_rotation_x(30f64.to_radians());
+        let ry = DMat4::from_rotation_y(45f64.to_radians());
+        let rz = DMat4::from_rotation_z(60f64.to_radians());
+
+        // rotateXYZ applies X first, so X ends up rightmost
+        let xyz = compose_xform_ops(&[(op("xformOp:rotateXYZ"), XformOpValue::Vec3(angles))]);
+        assert_dmat4_eq(xyz.unwrap(), rz * ry * rx);
+
+        let zyx = compose_xform_ops(&[(op("xformOp:rotateZYX"), XformOpValue::Vec3(angles))]);
+        assert_dmat4_eq(zyx.unwrap(), rx * ry * rz);
+
+        let yzx = compose_xform_ops(&[(op("xformOp:rotateYZX"), XformOpValue::Vec3(angles))]);
+        assert_dmat4_eq(yzx.unwrap(), rx * rz * ry);
+    }
+
+    #[test]
+    fn orient_op() {
+        let q = DQuat::from_rotation_y(0.7);
+        let m = compose_xform_ops(&[(
+            op("xformOp:orient"),
+            XformOpValue::Quat([q.x, q.y, q.z, q.w]),
+        )])
+        .unwrap();
+        assert_dmat4_eq(m, DMat4::from_quat(q));
+    }
+
+    #[test]
+    fn transform_op_is_row_vector() {
+        let mut rows = DMat4::IDENTITY.to_cols_array_2d();
+        rows[3] = [5.0, 6.0, 7.0, 1.0];
+        let m = compose_xform_ops(&[(op("xformOp:transform"), XformOpValue::Matrix(rows))]);
+        assert_dmat4_eq(
+            m.unwrap(),
+            DMat4::from_translation(DVec3::new(5.0, 6.0, 7.0)),
+        );
+    }
+
+    #[test]
+    fn pivot_rotation() {
+        // rotating about a pivot at (1, 0, 0) keeps the pivot in place
+        let m = compose_xform_ops(&[
+            (
+                op("xformOp:translate:pivot"),
+                XformOpValue::Vec3([1.0, 0.0, 0.0]),
+            ),
+            (op("xformOp:rotateZ"), XformOpValue::Scalar(90.0)),
+            (
+                op("!invert!xformOp:translate:pivot"),
+                XformOpValue::Vec3([1.0, 0.0, 0.0]),
+            ),
+        ])
+        .unwrap();
+        let pivot = m.transform_point3(DVec3::new(1.0, 0.0, 0.0));
+        assert!((pivot - DVec3::new(1.0, 0.0, 0.0)).length() < 1e-9);
+        let p = m.transform_point3(DVec3::new(2.0, 0.0, 0.0));
+        assert!((p - DVec3::new(1.0, 1.0, 0.0)).length() < 1e-9);
+    }
+
+    #[test]
+    fn mismatched_value_type_is_an_error() {
+        let result = compose_xform_ops(&[(op("xformOp:translate"), XformOpValue::Scalar(1.0))]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn singular_inverted_op_is_an_error() {
+        let flat = XformOpValue::Vec3([1.0, 0.0, 1.0]);
+        assert!(compose_xform_ops(&[(op("xformOp:scale"), flat)]).is_ok());
+        let err = compose_xform_ops(&[(op("!invert!xformOp:scale"), flat)]).unwrap_err();
+        assert_eq!(err, "!invert!xformOp:scale is singular");
+    }
+
+    fn binding(material: &str, stronger: bool) -> MaterialBinding {
+        MaterialBinding {
+            material: material.to_string(),
+            stronger,
+        }
+    }
+
+    /// Scope for a chain of prims, outermost first, each with an all-purpose binding.
+    fn scope_chain(levels: Vec<BindingLevel>) -> Option<Rc<BindingScope>> {
+        levels.into_iter().fold(None, |parent, level| {
+            Some(Rc::new(BindingScope { level, parent }))
+        })
+    }
+
+    fn direct(material: &str, stronger: bool) -> BindingLevel {
+        BindingLevel {
+            direct: [None, Some(binding(material, stronger))],
+            ..BindingLevel::default()
+        }
+    }
+
+    #[test]
+    fn collection_membership() {
+        let collection = Collection {
+            includes: vec!["/World/Set".to_string()],
+            excludes: vec!["/World/Set/Hero".to_string()],
+            expand: true,
+        };
+        assert!(collection.contains("/World/Set"));
+        assert!(collection.contains("/World/Set/Tree"));
+        assert!(!collection.contains("/World/Set/Hero/Body"));
+        assert!(!collection.contains("/World/SetDressing"));
+
+        let explicit = Collection {
+            expand: false,
+            ..collection
+        };
+        assert!(!explicit.contains("/World/Set/Tree"));
+    }
+
+    #[test]
+    fn nearest_binding_wins() {
+        let scope = scope_chain(vec![
+            direct("/Looks/Outer", false),
+            direct("/Looks/Inner", false),
+        ]);
+        assert_eq!(
+            resolve_binding(scope.as_ref(), "/World/Geo").as_deref(),
+            Some("/Looks/Inner")
+        );
+    }
+
+    #[test]
+    fn stronger_ancestor_binding_wins() {
+        let scope = scope_chain(vec![
+            direct("/Looks/Outer", true),
+            direct("/Looks/Inner", false),
+        ]);
+        assert_eq!(
+            resolve_binding(scope.as_ref(), "/World/Geo").as_deref(),
+            Some("/Looks/Outer")
+        );
+    }
+
+    #[test]
+    fn collection_binding_beats_direct_binding() {
+        let mut level = direct("/Looks/Direct", false);
+        level.collections[1].push(CollectionBinding {
+            collection: Collection {
+                includes: vec!["/World/Geo".to_string()],
+                excludes: Vec::new(),
+                expand: true,
+            },
+            binding: binding("/Looks/Collection", false),
+        });
+        let scope = scope_chain(vec![level]);
+        assert_eq!(
+            resolve_binding(scope.as_ref(), "/World/Geo/Mesh").as_deref(),
+            Some("/Looks/Collection")
+        );
+        assert_eq!(
+            resolve_binding(scope.as_ref(), "/World/Other").as_deref(),
+            Some("/Looks/Direct")
+        );
+    }
+
+    #[test]
+    fn instance_bindings_resolve_per_mesh() {
+        // bindings inside the prototype, then the ones around each instance
+        let inside = scope_chain(vec![direct("/Looks/Inner", false)]);
+        let stronger = scope_chain(vec![direct("/Looks/Outer", true)]);
+        let weaker = scope_chain(vec![direct("/Looks/Outer", false)]);
+        let resolve = |outer: &Option<Rc<BindingScope>>, path| {
+            resolve_binding_in(&[inside.as_ref(), outer.as_ref()], path)
+        };
+        assert_eq!(
+            resolve(&stronger, "/World/A/Body").as_deref(),
+            Some("/Looks/Outer")
+        );
+        assert_eq!(
+            resolve(&weaker, "/World/B/Body").as_deref(),
+            Some("/Looks/Inner")
+        );
+
+        let mut picked = BindingLevel::default();
+        picked.collections[1].push(CollectionBinding {
+            collection: Collection {
+                includes: vec!["/World/B/Bare".to_string()],
+                excludes: Vec::new(),
+                expand: true,
+            },
+            binding: binding("/Looks/Picked", false),
+        });
+        let around = scope_chain(vec![picked]);
+        let bare = |path| resolve_binding_in(&[None, around.as_ref()], path);
+        assert_eq!(bare("/World/B/Bare").as_deref(), Some("/Looks/Picked"));
+        assert_eq!(bare("/World/C/Bare"), None);
+
+        // nested prototypes chain their scopes instead
+        let chained = chain_scopes(inside.as_ref(), stronger.as_ref());
+        assert_eq!(
+            resolve_binding(chained.as_ref(), "/World/A/Body").as_deref(),
+            Some("/Looks/Outer")
+        );
+        assert!(Rc::ptr_eq(
+            &chain_scopes(None, stronger.as_ref()).unwrap(),
+            stronger.as_ref().unwrap()
+        ));
+    }
+
+    #[test]
+    fn instances_resolve_materials_for_their_own_meshes() {
+        let material = |name: &str| {
+            format!(
+                "def Material \"{name}\" {{\n\
+                 def Shader \"Surface\" {{\n\
+                 uniform token info:id = \"UsdPreviewSurface\"\n}}\n}}\n"
+            )
+        };
+        let stage = format!(
+            "def Scope \"Looks\" {{\n{}{}{}}}\n\
+             def Xform \"Asset\" {{\n{}{}}}\n\
+             def Xform \"World\" {{\n\
+             uniform token collection:picked:expansionRule = \"expandPrims\"\n\
+             rel collection:picked:includes = </World/B/Bare>\n\
+             rel material:binding:collection:picked = [</World.collection:picked>, </Looks/Picked>]\n\
+             def \"A\" (instanceable = true\n references = </Asset>) {{\n\
+             rel material:binding = </Looks/Outer> (bindMaterialAs = \"strongerThanDescendants\")\n}}\n\
+             def \"B\" (instanceable = true\n references = </Asset>) {{\n}}\n\
+             def \"C\" (instanceable = true\n references = </Asset>) {{\n}}\n}}\n",
+            material("Inner"),
+            material("Outer"),
+            material("Picked"),
+            usda_triangle("Body", 1.0, "", "rel material:binding = </Looks/Inner>"),
+            usda_triangle("Bare", 2.0, "", ""),
+        );
+        let scene = load_usda("instance_bindings", &stage, &LoadOptions::default());
+        let placed: Vec<(f32, Option<&str>)> = scene
+            .instances
+            .iter()
+            .map(|instance| {
+                let material = instance
+                    .material_index
+                    .map(|index| scene.materials[index].path.as_str());
+                (scene.meshes[instance.mesh_index].positions[0][0], material)
+            })
+            .collect();
+        assert_eq!(
+            placed,
+            vec![
+                // the Asset prim itself, then instances A, B and C
+                (1.0, Some("/Looks/Inner")),
+                (2.0, None),
+                (1.0, Some("/Looks/Outer")),
+                (2.0, Some("/Looks/Outer")),
+                (1.0, Some("/Looks/Inner")),
+                (2.0, Some("/Looks/Picked")),
+                (1.0, Some("/Looks/Inner")),
+                (2.0, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn preview_purpose_is_preferred() {
+        let outer = BindingLevel {
+            direct: [Some(binding("/Looks/Preview", false)), None],
+            ..BindingLevel::default()
+        };
+        let scope = scope_chain(vec![outer, direct("/Looks/Full", false)]);
+        assert_eq!(
+            resolve_binding(scope.as_ref(), "/World/Geo").as_deref(),
+            Some("/Looks/Preview")
+        );
+    }
+
+    #[test]
+    fn asset_paths_resolve_against_their_layer() {
+        let layer = "/assets/props/chair.usda";
+        assert_eq!(
+            resolve_asset_path(layer, "./textures/wood.png"),
+            "/assets/props/textures/wood.png"
+        );
+        assert_eq!(
+            resolve_asset_path(layer, "wood.png"),
+            "/assets/props/wood.png"
+        );
+        assert_eq!(
+            resolve_asset_path(layer, "/shared/wood.png"),
+            "/shared/wood.png"
+        );
+        assert_eq!(
+            resolve_asset_path(layer, "C:/shared/wood.png"),
+            "C:/shared/wood.png"
+        );
+        assert_eq!(resolve_asset_path("chair.usda", "./wood.png"), "wood.png");
+    }
+
+    #[test]
+    fn texture_tokens() {
+        assert_eq!(
+            TextureChannel::from_output("outputs:rgb"),
+            Some(TextureChannel::Rgb)
+        );
+        assert_eq!(TextureChannel::from_output("outputs:surface"), None);
+        assert_eq!(TextureWrap::from_token("mirror"), TextureWrap::Mirror);
+        assert_eq!(TextureWrap::from_token(""), TextureWrap::UseMetadata);
+        assert_eq!(SourceColorSpace::from_token("sRGB"), SourceColorSpace::Srgb);
+    }
+
+    #[test]
+    fn instance_transform_composes_with_animated_prototype() {
+        let translate = |x: f32| make_trs_matrix([x, 0.0, 0.0], [0.0, 0.0, 0.0, 1.0], [1.0; 3]);
+        let instance = WorldTransform {
+            current: translate(10.0),
+            animation: None,
+        };
+        let placed = WorldTransform {
+            current: translate(1.0),
+            animation: Some(Animation::new(vec![translate(1.0), translate(2.0)])),
+        };
+
+        let world = instance.post_mult(&placed);
+        let x = |m: &Matrix4d| matrix4d_to_mat4(m).w_axis.x;
+        assert!(approx_eq(x(&world.current), 11.0));
+        let animation = world.animation.expect("animation is kept");
+        assert!(approx_eq(x(&animation.frames[0]), 11.0));
+        assert!(approx_eq(x(&animation.frames[1]), 12.0));
+
+        let still = instance.post_mult(&WorldTransform::identity());
+        assert!(still.animation.is_none());
+    }
+
+    #[test]
+    fn shared_animations_emit_one_track() {
+        let translate = |x: f32| make_trs_matrix([x, 0.0, 0.0], [0.0, 0.0, 0.0, 1.0], [1.0; 3]);
+        let animated = || WorldTransform {
+            current: translate(1.0),
+            animation: Some(Animation::new(vec![translate(1.0), translate(2.0)])),
+        };
+        let options = LoadOptions::default();
+        let mut builder = SceneBuilder::new(&options);
+
+        let shared = animated();
+        let first = builder.instance_transform(&shared);
+        assert_eq!(builder.instance_transform(&shared.clone()), first);
+        // equal poses sampled separately still get their own track
+        assert_ne!(builder.instance_transform(&animated()), first);
+        assert_eq!(builder.data.transform_tracks.len(), 2);
+    }
+
+    #[test]
+    fn instancer_groups_points_and_masks_ids() {
+        let points = InstancerPoints {
+            proto_indices: vec![1, 0, 1, 5, 1],
+            ids: Some(vec![10, 11, 12, 13, 14]),
+            masked_ids: [12].into_iter().collect(),
+            ..InstancerPoints::default()
+        };
+        let (groups, out_of_range) = points.group_by_prototype(2);
+        assert_eq!(groups, vec![vec![1], vec![0, 4]]);
+        assert_eq!(out_of_range, Some(5));
+
+        // without ids the point index is the id
+        let points = InstancerPoints {
+            proto_indices: vec![0, 0],
+            masked_ids: [0].into_iter().collect(),
+            ..InstancerPoints::default()
+        };
+        assert_eq!(points.group_by_prototype(1).0, vec![vec![1]]);
+    }
+
+    #[test]
+    fn inactive_ids_apply_their_list_op() {
+        let mut ids: HashSet<i64> = [1, 2, 3].into();
+        let op = IdListOp {
+            explicit: None,
+            deleted: vec![2, 5],
+            added: vec![5, 7],
+        };
+        op.apply(&mut ids);
+        assert_eq!(ids, HashSet::from([1, 3, 5, 7]));
+        let explicit = IdListOp {
+            explicit: Some(vec![4]),
+            ..IdListOp::default()
+        };
+        explicit.apply(&mut ids);
+        assert_eq!(ids, HashSet::from([4]));
+
+        // both masks match ids rather than point indices
+        let inactive = IdListOp {
+            added: vec![13],
+            ..IdListOp::default()
+        };
+        let points = InstancerPoints {
+            proto_indices: vec![0; 4],
+            ids: Some(vec![13, 12, 11, 10]),
+            masked_ids: masked_ids(vec![11], Some(&inactive)),
+            ..InstancerPoints::default()
+        };
+        assert_eq!(points.group_by_prototype(1).0, vec![vec![1, 3]]);
+    }
+
+    #[test]
+    fn instancer_velocities_extrapolate() {
+        let points = InstancerPoints {
+            proto_indices: vec![0],
+            positions: vec![[1.0, 0.0, 0.0]],
+            velocities: vec![[2.0, 0.0, 0.0]],
+            angular_velocities: vec![[0.0, 90.0, 0.0]],
+            velocity_seconds: 0.5,
+            ..InstancerPoints::default()
+        };
+        let mat = matrix4d_to_mat4(&points.transform(0));
+        assert!(approx_eq(mat.w_axis.x, 2.0));
+        // 45 degrees about +Y turns +X towards -Z
+        let x_axis = mat.transform_vector3(Vec3::X);
+        assert!(approx_eq(x_axis.x, std::f32::consts::FRAC_1_SQRT_2));
+        assert!(approx_eq(x_axis.z, -std::f32::consts::FRAC_1_SQRT_2));
+    }
+
+    fn quad(offset: f32) -> MeshData {
+        MeshData {
+            positions: vec![
+                [offset, 0.0, 0.0],
+                [offset + 1.0, 0.0, 0.0],
+                [offset + 1.0, 1.0, 0.0],
+                [offset, 1.0, 0.0],
+            ],
+            face_vertex_counts: vec![4],
+            face_vertex_indices: vec![0, 1, 2, 3],
+            primvars: HashMap::new(),
+            double_sided: false,
+            material_index: None,
+            subdivision: SubdivisionData::default(),
+            hole_indices: Vec::new(),
+            orientation: Orientation::RightHanded,
+        }
+    }
+
+    #[test]
+    fn dedup_merges_identical_meshes() {
+        let instance = |mesh_index| MeshInstance {
+            mesh_index,
+            transform: InstanceTransform::Static(matrix4d_to_f32_array(&Matrix4d::identity())),
+            material_index: None,
+        };
+        let mut scene = SceneData {
+            meshes: vec![quad(0.0), quad(5.0), quad(0.0), quad(5.0)],
+            instances: (0..4).map(instance).collect(),
+            ..SceneData::default()
+        };
+
+        let report = dedup_meshes(&mut scene);
+        assert_eq!(report.meshes_before, 4);
+        assert_eq!(report.meshes_after, 2);
+        assert_eq!(report.bytes_saved, 2 * mesh_bytes(&quad(0.0)));
+        let indices: Vec<usize> = scene.instances.iter().map(|i| i.mesh_index).collect();
+        assert_eq!(indices, vec![0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn dedup_keeps_meshes_with_different_materials() {
+        let mut bound = quad(0.0);
+        bound.material_index = Some(0);
+        let mut scene = SceneData {
+            meshes: vec![quad(0.0), bound],
+            ..SceneData::default()
+        };
+        assert_eq!(dedup_meshes(&mut scene).meshes_after, 2);
+    }
+
+    #[test]
+    fn z_up_centimetres_become_y_up_metres() {
+        let metadata = StageMetadata {
+            up_axis: UpAxis::Z,
+            meters_per_unit: 0.01,
+            ..StageMetadata::default()
+        };
+        let correction = metadata.root_correction();
+        let up = correction.transform_point3(DVec3::new(0.0, 0.0, 100.0));
+        assert!((up - DVec3::new(0.0, 1.0, 0.0)).length() < 1e-9);
+        // USD's +Y forward ends up pointing away from a default Bevy camera
+        let forward = correction.transform_vector3(DVec3::Y);
+        assert!((forward - DVec3::new(0.0, 0.0, -0.01)).length() < 1e-9);
+    }
+
+    #[test]
+    fn unauthored_units_are_centimetres() {
+        let stage = usda_triangle("Tri", 0.0, "", "");
+        let scale = |scene: &SceneData| match scene.instances[0].transform {
+            InstanceTransform::Static(matrix) => matrix[0][0],
+            InstanceTransform::Animated(_) => panic!("expected a static transform"),
+        };
+
+        let scene = load_usda("unauthored_units", &stage, &LoadOptions::default());
+        assert_eq!(scene.metadata.meters_per_unit, 0.01);
+        assert!((scale(&scene) - 0.01).abs() < 1e-6);
+
+        let options = LoadOptions {
+            correct_units_and_axis: false,
+            ..LoadOptions::default()
+        };
+        let scene = load_usda("unauthored_units_kept", &stage, &options);
+        assert!((scale(&scene) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn y_up_metres_need_no_correction() {
+        let metadata = StageMetadata {
+            meters_per_unit: 1.0,
+            ..StageMetadata::default()
+        };
+        assert_dmat4_eq(metadata.root_correction(), DMat4::IDENTITY);
+
+        let centimetres = StageMetadata::default().root_correction();
+        assert_dmat4_eq(centimetres, DMat4::from_scale(DVec3::splat(0.01)));
+    }
+
+    #[test]
+    fn cube_maps_uniform_primvars_onto_its_faces() {
+        let stage = "def Cube \"Block\" {\n\
+                     color3f[] primvars:displayColor = [(1, 0, 0), (0, 1, 0)] (\n\
+                     interpolation = \"uniform\"\n)\n\
+                     int[] primvars:displayColor:indices = [0, 0, 0, 0, 1, 0]\n\
+                     float[] primvars:weight = [1, 2, 3, 4, 5, 6, 7, 8] (\n\
+                     interpolation = \"vertex\"\n)\n}\n";
+        let scene = load_usda("cube_primvars", stage, &LoadOptions::default());
+        let colors = scene.meshes[0].primvars["displayColor"]
+            .as_float3()
+            .unwrap();
+        // USD's fifth face is +X, the first face of the generated cube
+        assert_eq!(colors.indices.as_deref(), Some(&[1, 0, 0, 0, 0, 0][..]));
+        assert!(matches!(
+            scene.diagnostics.as_slice(),
+            [UsdLoadError::DroppedPrimvar { name, .. }] if name == "weight"
+        ));
+
+        let mut unindexed = PrimvarData::Int(Primvar {
+            values: vec![0, 1, 2, 3, 4, 5],
+            indices: None,
+            interpolation: PrimvarInterpolation::Uniform,
+            element_size: 1,
+            type_name: "int[]".to_string(),
+        });
+        reorder_faces(&mut unindexed, &gprims::CUBE_USD_FACES).unwrap();
+        let unindexed = match unindexed {
+            PrimvarData::Int(p) => p,
+            _ => unreachable!(),
+        };
+        assert_eq!(unindexed.indices, Some(gprims::CUBE_USD_FACES.to_vec()));
+    }
+
+    #[test]
+    fn distant_lights_default_to_sunlight() {
+        let stage = "def DistantLight \"Sun\" {\n}\n\
+                     def SphereLight \"Bulb\" {\n}\n\
+                     def DistantLight \"Dim\" {\n\
+                     float inputs:intensity = 2\n}\n";
+        let scene = load_usda("distant_light", stage, &LoadOptions::default());
+        let intensities: Vec<f32> = scene.lights.iter().map(|light| light.intensity).collect();
+        assert_eq!(intensities, vec![50_000.0, 1.0, 2.0]);
+        assert_eq!(scene.lights[0].kind, LightKind::Distant { angle: 0.53 });
+    }
+
+    #[test]
+    fn color_temperature_tints_lights() {
+        let white = blackbody_rgb(6504.0);
+        assert!(white.iter().all(|c| (c - 1.0).abs() < 0.05), "{white:?}");
+
+        let light = LightData {
+            path: "/light".to_string(),
+            kind: LightKind::Sphere { radius: 0.5 },
+            intensity: 10.0,
+            exposure: 2.0,
+            color: [1.0, 1.0, 1.0],
+            color_temperature: Some(2700.0),
+            cone: None,
+            transform: InstanceTransform::Static([[0.0; 4]; 4]),
+        };
+        assert_eq!(light.scaled_intensity(), 40.0);
+        let [r, g, b] = light.linear_color();
+        assert!(r > g && g > b, "{:?}", [r, g, b]);
+        // normalized so the tint does not change brightness
+        assert!((0.2126 * r + 0.7152 * g + 0.0722 * b - 1.0).abs() < 1e-4);
+    }
+
     #[test]
     fn matrix_roundtrip() {
         let mat = Mat4::from_scale_rotation_translation(
@@ -0,0 +1,849 @@
+//! Uniform refinement of subdivision surfaces.
+//!
+//! Each level splits every face and moves the points towards the limit
+//! surface of the mesh's `subdivisionScheme`. Creases and corners follow the
+//! semi-sharp rules: a tag loses one unit of sharpness per level and blends
+//! between the smooth and sharp rules while it is below one.
+
+use std::collections::HashMap;
+
+use crate::open_rs_loader::{
+    InterpolateBoundary, MeshData, Primvar, PrimvarData, PrimvarInterpolation, SubdivisionData,
+    SubdivisionScheme,
+};
+
+/// Weighted sum of the points (or face corners) of the previous level.
+type Stencil = Vec<(usize, f32)>;
+
+/// Smoothing rule of a refinement, Loop needs an all-triangle mesh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rules {
+    CatmullClark,
+    Loop,
+    Bilinear,
+}
+
+/// Topology and sharpness tags of one level.
+#[derive(Debug)]
+struct Level {
+    point_count: usize,
+    faces: Vec<Vec<usize>>,
+    /// Edge sharpness keyed by the sorted point pair.
+    creases: HashMap<[usize; 2], f32>,
+    corners: HashMap<usize, f32>,
+}
+
+struct Edge {
+    points: [usize; 2],
+    faces: Vec<usize>,
+    sharpness: f32,
+}
+
+/// How the points and faces of one level derive from the previous one.
+struct Refinement {
+    /// New points from the old ones, following the scheme.
+    smooth: Vec<Stencil>,
+    /// New points from the old ones, linearly.
+    linear: Vec<Stencil>,
+    /// Parent face of each new face and its corners from the parent's corners.
+    children: Vec<(usize, Vec<Stencil>)>,
+}
+
+fn edge_key(a: usize, b: usize) -> [usize; 2] {
+    [a.min(b), a.max(b)]
+}
+
+fn scaled(stencil: &[(usize, f32)], weight: f32) -> impl Iterator<Item = (usize, f32)> + '_ {
+    stencil.iter().map(move |&(i, w)| (i, w * weight))
+}
+
+/// `(1 - t) * a + t * b`
+fn blend(a: &[(usize, f32)], b: &[(usize, f32)], t: f32) -> Stencil {
+    scaled(a, 1.0 - t).chain(scaled(b, t)).collect()
+}
+
+fn average(points: &[usize]) -> Stencil {
+    let weight = 1.0 / points.len() as f32;
+    points.iter().map(|&p| (p, weight)).collect()
+}
+
+impl Level {
+    fn from_mesh(mesh: &MeshData, faces: Vec<Vec<usize>>) -> Self {
+        let tags = &mesh.subdivision;
+        let mut creases = HashMap::new();
+        for &([a, b], sharpness) in &tags.crease_edges {
+            if sharpness > 0.0 {
+                let entry = creases.entry(edge_key(a, b)).or_insert(0.0_f32);
+                *entry = entry.max(sharpness);
+            }
+        }
+        let mut corners = HashMap::new();
+        for &(point, sharpness) in &tags.corners {
+            if sharpness > 0.0 {
+                let entry = corners.entry(point).or_insert(0.0_f32);
+                *entry = entry.max(sharpness);
+            }
+        }
+        Level {
+            point_count: mesh.positions.len(),
+            faces,
+            creases,
+            corners,
+        }
+    }
+
+    fn refine(&self, rules: Rules, boundary: InterpolateBoundary) -> (Refinement, Level) {
+        // edges, and what touches every point
+        let mut edge_index: HashMap<[usize; 2], usize> = HashMap::new();
+        let mut edges: Vec<Edge> = Vec::new();
+        let mut face_edges: Vec<Vec<usize>> = Vec::with_capacity(self.faces.len());
+        let mut point_faces: Vec<Vec<usize>> = vec![Vec::new(); self.point_count];
+        let mut point_edges: Vec<Vec<usize>> = vec![Vec::new(); self.point_count];
+        for (face, points) in self.faces.iter().enumerate() {
+            let n = points.len();
+            let mut sides = Vec::with_capacity(n);
+            for i in 0..n {
+                let (a, b) = (points[i], points[(i + 1) % n]);
+                let key = edge_key(a, b);
+                let edge = *edge_index.entry(key).or_insert_with(|| {
+                    edges.push(Edge {
+                        points: key,
+                        faces: Vec::new(),
+                        sharpness: self.creases.get(&key).copied().unwrap_or(0.0),
+                    });
+                    point_edges[a].push(edges.len() - 1);
+                    point_edges[b].push(edges.len() - 1);
+                    edges.len() - 1
+                });
+                edges[edge].faces.push(face);
+                sides.push(edge);
+                point_faces[a].push(face);
+            }
+            face_edges.push(sides);
+        }
+        // boundaries and non-manifold edges are infinitely sharp
+        for edge in &mut edges {
+            if edge.faces.len() != 2 {
+                edge.sharpness = f32::INFINITY;
+            }
+        }
+
+        let face_points: Vec<Stencil> = self.faces.iter().map(|f| average(f)).collect();
+        let has_face_points = rules != Rules::Loop;
+        let edge_offset = self.point_count;
+        let face_offset = edge_offset + edges.len();
+
+        // ---- edge points
+        let mut smooth = Vec::with_capacity(face_offset + self.faces.len());
+        let mut linear = Vec::with_capacity(smooth.capacity());
+        let mut edge_points = Vec::with_capacity(edges.len());
+        let mut edge_midpoints = Vec::with_capacity(edges.len());
+        for edge in &edges {
+            let [a, b] = edge.points;
+            let sharp = average(&[a, b]);
+            let rule = if edge.sharpness >= 1.0 || rules == Rules::Bilinear {
+                sharp.clone()
+            } else {
+                let (f0, f1) = (edge.faces[0], edge.faces[1]);
+                let soft: Stencil = match rules {
+                    Rules::Loop => {
+                        let opposite = |face: usize| {
+                            self.faces[face]
+                                .iter()
+                                .copied()
+                                .find(|&p| p != a && p != b)
+                                .unwrap_or(a)
+                        };
+                        vec![
+                            (a, 0.375),
+                            (b, 0.375),
+                            (opposite(f0), 0.125),
+                            (opposite(f1), 0.125),
+                        ]
+                    }
+                    _ => [(a, 0.25), (b, 0.25)]
+                        .into_iter()
+                        .chain(scaled(&face_points[f0], 0.25))
+                        .chain(scaled(&face_points[f1], 0.25))
+                        .collect(),
+                };
+                blend(&soft, &sharp, edge.sharpness)
+            };
+            edge_points.push(rule);
+            edge_midpoints.push(sharp);
+        }
+
+        // ---- vertex points
+        for point in 0..self.point_count {
+            linear.push(vec![(point, 1.0)]);
+            let incident = &point_edges[point];
+            if incident.is_empty() || rules == Rules::Bilinear {
+                smooth.push(vec![(point, 1.0)]);
+                continue;
+            }
+            let other = |edge: usize| {
+                let [a, b] = edges[edge].points;
+                if a == point {
+                    b
+                } else {
+                    a
+                }
+            };
+
+            let sharp_edges: Vec<usize> = incident
+                .iter()
+                .copied()
+                .filter(|&e| edges[e].sharpness > 0.0)
+                .collect();
+            let on_boundary = incident.iter().any(|&e| edges[e].faces.len() != 2);
+            let mut corner = self.corners.get(&point).copied().unwrap_or(0.0);
+            // a boundary point with a single face is a corner
+            if boundary == InterpolateBoundary::EdgeAndCorner
+                && on_boundary
+                && point_faces[point].len() == 1
+            {
+                corner = f32::INFINITY;
+            }
+
+            let n = incident.len() as f32;
+            let soft: Option<Stencil> = (!on_boundary).then(|| match rules {
+                Rules::Loop => {
+                    let c = 0.375 + 0.25 * (std::f32::consts::TAU / n).cos();
+                    let beta = (0.625 - c * c) / n;
+                    std::iter::once((point, 1.0 - n * beta))
+                        .chain(incident.iter().map(|&e| (other(e), beta)))
+                        .collect()
+                }
+                _ => {
+                    // (Q + 2R + (n - 3) v) / n
+                    let faces = &point_faces[point];
+                    let q = 1.0 / (n * faces.len() as f32);
+                    let r = 1.0 / (n * n);
+                    std::iter::once((point, (n - 3.0) / n))
+                        .chain(faces.iter().flat_map(|&f| scaled(&face_points[f], q)))
+                        .chain(incident.iter().flat_map(|&e| [(point, r), (other(e), r)]))
+                        .collect()
+                }
+            });
+
+            let (sharp, sharpness): (Stencil, f32) = if corner > 0.0 || sharp_edges.len() > 2 {
+                let sharpness = if corner > 0.0 {
+                    corner
+                } else {
+                    sharp_edges.iter().map(|&e| edges[e].sharpness).sum::<f32>()
+                        / sharp_edges.len() as f32
+                };
+                (vec![(point, 1.0)], sharpness)
+            } else if sharp_edges.len() == 2 {
+                let (e0, e1) = (sharp_edges[0], sharp_edges[1]);
+                (
+                    vec![(point, 0.75), (other(e0), 0.125), (other(e1), 0.125)],
+                    (edges[e0].sharpness + edges[e1].sharpness) / 2.0,
+                )
+            } else {
+                // smooth, or a dart which refines like a smooth point
+                (Vec::new(), 0.0)
+            };
+
+            smooth.push(match soft {
+                Some(soft) if sharpness < 1.0 => {
+                    if sharp.is_empty() {
+                        soft
+                    } else {
+                        blend(&soft, &sharp, sharpness)
+                    }
+                }
+                _ if sharp.is_empty() => vec![(point, 1.0)],
+                _ => sharp,
+            });
+        }
+        smooth.extend(edge_points);
+        linear.extend(edge_midpoints);
+        if has_face_points {
+            smooth.extend(face_points.iter().cloned());
+            linear.extend(face_points);
+        }
+
+        // ---- new faces, winding as the parent
+        let mut children = Vec::new();
+        let mut faces = Vec::new();
+        for (face, points) in self.faces.iter().enumerate() {
+            let n = points.len();
+            let sides = &face_edges[face];
+            let half = |i: usize| vec![(i, 0.5), ((i + 1) % n, 0.5)];
+            if has_face_points {
+                let center: Stencil = (0..n).map(|i| (i, 1.0 / n as f32)).collect();
+                for i in 0..n {
+                    let prev = (i + n - 1) % n;
+                    faces.push(vec![
+                        points[i],
+                        edge_offset + sides[i],
+                        face_offset + face,
+                        edge_offset + sides[prev],
+                    ]);
+                    children.push((
+                        face,
+                        vec![vec![(i, 1.0)], half(i), center.clone(), half(prev)],
+                    ));
+                }
+            } else {
+                for i in 0..3 {
+                    let prev = (i + 2) % 3;
+                    faces.push(vec![
+                        points[i],
+                        edge_offset + sides[i],
+                        edge_offset + sides[prev],
+                    ]);
+                    children.push((face, vec![vec![(i, 1.0)], half(i), half(prev)]));
+                }
+                faces.push(sides.iter().map(|&e| edge_offset + e).collect());
+                children.push((face, vec![half(0), half(1), half(2)]));
+            }
+        }
+
+        // tags wear off by one per level
+        let mut creases = HashMap::new();
+        for (edge_point, edge) in edges.iter().enumerate() {
+            let sharpness = self.creases.get(&edge.points).copied().unwrap_or(0.0) - 1.0;
+            if sharpness > 0.0 {
+                let [a, b] = edge.points;
+                let mid = edge_offset + edge_point;
+                creases.insert(edge_key(a, mid), sharpness);
+                creases.insert(edge_key(mid, b), sharpness);
+            }
+        }
+        let corners = self
+            .corners
+            .iter()
+            .filter(|&(_, &sharpness)| sharpness > 1.0)
+            .map(|(&point, &sharpness)| (point, sharpness - 1.0))
+            .collect();
+
+        let level = Level {
+            point_count: if has_face_points {
+                face_offset + self.faces.len()
+            } else {
+                face_offset
+            },
+            faces,
+            creases,
+            corners,
+        };
+        (
+            Refinement {
+                smooth,
+                linear,
+                children,
+            },
+            level,
+        )
+    }
+}
+
+// -------- Primvars --------
+/// Values that can be blended with weights.
+trait Blend: Copy {
+    fn weighted(values: impl Iterator<Item = (Self, f32)>) -> Self;
+}
+
+impl Blend for f32 {
+    fn weighted(values: impl Iterator<Item = (Self, f32)>) -> Self {
+        values.map(|(v, w)| v * w).sum()
+    }
+}
+
+impl<const N: usize> Blend for [f32; N] {
+    fn weighted(values: impl Iterator<Item = (Self, f32)>) -> Self {
+        let mut out = [0.0; N];
+        for (value, weight) in values {
+            for (o, v) in out.iter_mut().zip(value) {
+                *o += v * weight;
+            }
+        }
+        out
+    }
+}
+
+/// What a primvar is stored per, once its indices are resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Storage {
+    Vertex,
+    Varying,
+    FaceVarying,
+    Uniform,
+    Constant,
+}
+
+/// Elements of `primvar` laid out per [`Storage`], or `None` when they do
+/// not fit the topology.
+fn resolve<T: Copy>(
+    primvar: &Primvar<T>,
+    point_count: usize,
+    face_vertex_counts: &[usize],
+    kept_faces: &[usize],
+) -> Option<(Storage, Vec<T>)> {
+    let wedge_count: usize = face_vertex_counts.iter().sum();
+    let slots = primvar
+        .indices
+        .as_ref()
+        .map_or(primvar.element_count(), Vec::len);
+    let storage = match primvar.interpolation {
+        PrimvarInterpolation::Constant => return Some((Storage::Constant, Vec::new())),
+        PrimvarInterpolation::Vertex if slots == point_count => Storage::Vertex,
+        PrimvarInterpolation::Varying if slots == point_count => Storage::Varying,
+        PrimvarInterpolation::FaceVarying if slots == wedge_count => Storage::FaceVarying,
+        // per-wedge data tagged as per-vertex, as accepted by the converter
+        PrimvarInterpolation::Vertex | PrimvarInterpolation::Varying if slots == wedge_count => {
+            Storage::FaceVarying
+        }
+        PrimvarInterpolation::Uniform if slots == face_vertex_counts.len() => Storage::Uniform,
+        _ => return None,
+    };
+
+    let slot_list: Vec<usize> = match storage {
+        Storage::FaceVarying => {
+            let mut starts = Vec::with_capacity(face_vertex_counts.len());
+            let mut cursor = 0;
+            for &count in face_vertex_counts {
+                starts.push(cursor);
+                cursor += count;
+            }
+            kept_faces
+                .iter()
+                .flat_map(|&f| starts[f]..starts[f] + face_vertex_counts[f])
+                .collect()
+        }
+        Storage::Uniform => kept_faces.to_vec(),
+        _ => (0..slots).collect(),
+    };
+    let size = primvar.element_size.max(1);
+    let mut values = Vec::with_capacity(slot_list.len() * size);
+    for slot in slot_list {
+        let element = primvar
+            .indices
+            .as_ref()
+            .map_or(slot, |indices| indices[slot]);
+        values.extend_from_slice(primvar.values.get(element * size..(element + 1) * size)?);
+    }
+    Some((storage, values))
+}
+
+/// Element `k` of the output is `stencils[k]` applied to `values`.
+fn apply<T: Blend>(stencils: &[Stencil], values: &[T], size: usize) -> Vec<T> {
+    stencils
+        .iter()
+        .flat_map(|stencil| {
+            (0..size).map(move |component| {
+                T::weighted(
+                    stencil
+                        .iter()
+                        .map(|&(i, w)| (values[i * size + component], w)),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Face-varying values of the children from the corners of their parents.
+fn apply_corners<T: Blend>(
+    refinement: &Refinement,
+    parent_starts: &[usize],
+    values: &[T],
+    size: usize,
+) -> Vec<T> {
+    refinement
+        .children
+        .iter()
+        .flat_map(|(parent, corners)| {
+            let start = parent_starts[*parent];
+            corners.iter().flat_map(move |corner| {
+                (0..size).map(move |component| {
+                    T::weighted(
+                        corner
+                            .iter()
+                            .map(|&(i, w)| (values[(start + i) * size + component], w)),
+                    )
+                })
+            })
+        })
+        .collect()
+}
+
+/// Per-face values of the children, copied from their parents.
+fn inherit<T: Copy>(refinement: &Refinement, values: &[T], size: usize) -> Vec<T> {
+    refinement
+        .children
+        .iter()
+        .flat_map(|&(parent, _)| values[parent * size..(parent + 1) * size].iter().copied())
+        .collect()
+}
+
+/// Refine a primvar through every level, returning `None` for values that
+/// cannot be interpolated.
+fn refine_primvar<T: Blend>(
+    primvar: &Primvar<T>,
+    point_count: usize,
+    face_vertex_counts: &[usize],
+    kept_faces: &[usize],
+    steps: &[(Refinement, Vec<usize>)],
+) -> Option<Primvar<T>> {
+    let (storage, mut values) = resolve(primvar, point_count, face_vertex_counts, kept_faces)?;
+    if storage == Storage::Constant {
+        return Some(primvar.clone());
+    }
+    let size = primvar.element_size.max(1);
+    for (refinement, parent_starts) in steps {
+        values = match storage {
+            Storage::Vertex => apply(&refinement.smooth, &values, size),
+            Storage::Varying => apply(&refinement.linear, &values, size),
+            Storage::FaceVarying => apply_corners(refinement, parent_starts, &values, size),
+            Storage::Uniform | Storage::Constant => inherit(refinement, &values, size),
+        };
+    }
+    let interpolation = match storage {
+        Storage::Vertex => PrimvarInterpolation::Vertex,
+        Storage::Varying => PrimvarInterpolation::Varying,
+        Storage::FaceVarying => PrimvarInterpolation::FaceVarying,
+        Storage::Uniform => PrimvarInterpolation::Uniform,
+        Storage::Constant => PrimvarInterpolation::Constant,
+    };
+    Some(Primvar {
+        values,
+        indices: None,
+        interpolation,
+        element_size: primvar.element_size,
+        type_name: primvar.type_name.clone(),
+    })
+}
+
+/// Integer primvars can only be copied, per face or for the whole mesh.
+fn refine_int_primvar(
+    primvar: &Primvar<i32>,
+    point_count: usize,
+    face_vertex_counts: &[usize],
+    kept_faces: &[usize],
+    steps: &[(Refinement, Vec<usize>)],
+) -> Option<Primvar<i32>> {
+    let (storage, mut values) = resolve(primvar, point_count, face_vertex_counts, kept_faces)?;
+    let size = primvar.element_size.max(1);
+    match storage {
+        Storage::Constant => return Some(primvar.clone()),
+        Storage::Uniform => {}
+        _ => return None,
+    }
+    for (refinement, _) in steps {
+        values = inherit(refinement, &values, size);
+    }
+    Some(Primvar {
+        values,
+        indices: None,
+        interpolation: PrimvarInterpolation::Uniform,
+        element_size: primvar.element_size,
+        type_name: primvar.type_name.clone(),
+    })
+}
+
+// -------- Subdivision --------
+/// Refine `mesh` by `levels` with its subdivision scheme.
+///
+/// The result is a polygonal mesh: quads for Catmull-Clark and bilinear,
+/// triangles for Loop. Loop falls back to Catmull-Clark on meshes with other
+/// faces, and `interpolateBoundary = none` is refined like `edgeOnly`.
+/// Authored normals no longer fit the surface and are dropped; creases that
+/// are still sharp after the last level are kept on the result, and the
+/// children of holes stay holes.
+///
+/// The topology must be valid, faces with fewer than three points are dropped.
+pub fn subdivide(mesh: &MeshData, levels: u32) -> MeshData {
+    let rules = match mesh.subdivision.scheme {
+        SubdivisionScheme::None => return mesh.clone(),
+        _ if levels == 0 => return mesh.clone(),
+        SubdivisionScheme::Bilinear => Rules::Bilinear,
+        SubdivisionScheme::Loop if mesh.face_vertex_counts.iter().all(|&n| n == 3) => Rules::Loop,
+        SubdivisionScheme::Loop | SubdivisionScheme::CatmullClark => Rules::CatmullClark,
+    };
+
+    let mut kept_faces = Vec::with_capacity(mesh.face_vertex_counts.len());
+    let mut faces = Vec::with_capacity(mesh.face_vertex_counts.len());
+    let mut cursor = 0;
+    for (face, &count) in mesh.face_vertex_counts.iter().enumerate() {
+        if count >= 3 {
+            kept_faces.push(face);
+            faces.push(mesh.face_vertex_indices[cursor..cursor + count].to_vec());
+        }
+        cursor += count;
+    }
+
+    let mut level = Level::from_mesh(mesh, faces);
+    let mut holes: Vec<bool> = kept_faces
+        .iter()
+        .map(|face| mesh.hole_indices.binary_search(face).is_ok())
+        .collect();
+    let mut positions = mesh.positions.clone();
+    let mut steps = Vec::with_capacity(levels as usize);
+    for _ in 0..levels {
+        let parent_starts: Vec<usize> = level
+            .faces
+            .iter()
+            .scan(0, |cursor, face| {
+                let start = *cursor;
+                *cursor += face.len();
+                Some(start)
+            })
+            .collect();
+        let (refinement, next) = level.refine(rules, mesh.subdivision.interpolate_boundary);
+        positions = apply(&refinement.smooth, &positions, 1);
+        holes = inherit(&refinement, &holes, 1);
+        level = next;
+        steps.push((refinement, parent_starts));
+    }
+
+    let point_count = mesh.positions.len();
+    let counts = &mesh.face_vertex_counts;
+    let primvars = mesh
+        .primvars
+        .iter()
+        .filter(|(name, _)| name.as_str() != "normals")
+        .filter_map(|(name, primvar)| {
+            let refined = match primvar {
+                PrimvarData::Float(p) => {
+                    PrimvarData::Float(refine_primvar(p, point_count, counts, &kept_faces, &steps)?)
+                }
+                PrimvarData::Float2(p) => PrimvarData::Float2(refine_primvar(
+                    p,
+                    point_count,
+                    counts,
+                    &kept_faces,
+                    &steps,
+                )?),
+                PrimvarData::Float3(p) => PrimvarData::Float3(refine_primvar(
+                    p,
+                    point_count,
+                    counts,
+                    &kept_faces,
+                    &steps,
+                )?),
+                PrimvarData::Float4(p) => PrimvarData::Float4(refine_primvar(
+                    p,
+                    point_count,
+                    counts,
+                    &kept_faces,
+                    &steps,
+                )?),
+                PrimvarData::Int(p) => PrimvarData::Int(refine_int_primvar(
+                    p,
+                    point_count,
+                    counts,
+                    &kept_faces,
+                    &steps,
+                )?),
+            };
+            Some((name.clone(), refined))
+        })
+        .collect();
+
+    let mut crease_edges: Vec<([usize; 2], f32)> = level.creases.into_iter().collect();
+    crease_edges.sort_by_key(|&(edge, _)| edge);
+    let mut corners: Vec<(usize, f32)> = level.corners.into_iter().collect();
+    corners.sort_by_key(|&(point, _)| point);
+    MeshData {
+        positions,
+        face_vertex_counts: level.faces.iter().map(Vec::len).collect(),
+        face_vertex_indices: level.faces.into_iter().flatten().collect(),
+        primvars,
+        double_sided: mesh.double_sided,
+        material_index: mesh.material_index,
+        subdivision: SubdivisionData {
+            scheme: SubdivisionScheme::None,
+            interpolate_boundary: mesh.subdivision.interpolate_boundary,
+            crease_edges,
+            corners,
+        },
+        hole_indices: (0..holes.len()).filter(|&face| holes[face]).collect(),
+        orientation: mesh.orientation,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_rs_loader::Orientation;
+
+    fn mesh(positions: Vec<[f32; 3]>, faces: &[&[usize]], scheme: SubdivisionScheme) -> MeshData {
+        MeshData {
+            positions,
+            face_vertex_counts: faces.iter().map(|f| f.len()).collect(),
+            face_vertex_indices: faces.iter().flat_map(|f| f.iter().copied()).collect(),
+            primvars: HashMap::new(),
+            double_sided: false,
+            material_index: None,
+            subdivision: SubdivisionData {
+                scheme,
+                ..Default::default()
+            },
+            hole_indices: Vec::new(),
+            orientation: Orientation::RightHanded,
+        }
+    }
+
+    /// Unit cube with shared corners, point `i` sits at the bits of `i`.
+    fn cube(scheme: SubdivisionScheme) -> MeshData {
+        let positions = (0..8)
+            .map(|i| [i & 1, (i >> 1) & 1, (i >> 2) & 1].map(|bit| bit as f32))
+            .collect();
+        let faces: [&[usize]; 6] = [
+            &[0, 2, 3, 1],
+            &[4, 5, 7, 6],
+            &[0, 1, 5, 4],
+            &[2, 6, 7, 3],
+            &[0, 4, 6, 2],
+            &[1, 3, 7, 5],
+        ];
+        mesh(positions, &faces, scheme)
+    }
+
+    /// Two by two quads in the XY plane.
+    fn grid(boundary: InterpolateBoundary) -> MeshData {
+        let positions = (0..9)
+            .map(|i| [(i % 3) as f32, (i / 3) as f32, 0.0])
+            .collect();
+        let faces: [&[usize]; 4] = [&[0, 1, 4, 3], &[1, 2, 5, 4], &[3, 4, 7, 6], &[4, 5, 8, 7]];
+        let mut grid = mesh(positions, &faces, SubdivisionScheme::CatmullClark);
+        grid.subdivision.interpolate_boundary = boundary;
+        grid
+    }
+
+    fn radius(p: [f32; 3]) -> f32 {
+        p.map(|c| c - 0.5).iter().map(|c| c * c).sum::<f32>().sqrt()
+    }
+
+    #[test]
+    fn catmull_clark_rounds_a_cube() {
+        let refined = subdivide(&cube(SubdivisionScheme::CatmullClark), 2);
+        assert_eq!(refined.face_vertex_counts, vec![4; 96]);
+        assert_eq!(refined.positions.len(), 98);
+        assert_eq!(refined.subdivision.scheme, SubdivisionScheme::None);
+
+        let radii: Vec<f32> = refined.positions.iter().map(|&p| radius(p)).collect();
+        let max = radii.iter().copied().fold(0.0, f32::max);
+        let min = radii.iter().copied().fold(f32::MAX, f32::min);
+        assert!(max / min < 1.25, "{min} .. {max}");
+    }
+
+    #[test]
+    fn sharp_creases_keep_the_cube() {
+        let mut cube = cube(SubdivisionScheme::CatmullClark);
+        let mut edges = Vec::new();
+        let mut cursor = 0;
+        for &n in &cube.face_vertex_counts {
+            let face = &cube.face_vertex_indices[cursor..cursor + n];
+            for i in 0..n {
+                edges.push(([face[i], face[(i + 1) % n]], 10.0));
+            }
+            cursor += n;
+        }
+        cube.subdivision.crease_edges = edges;
+
+        let refined = subdivide(&cube, 2);
+        assert_eq!(&refined.positions[..8], &cube.positions[..]);
+        for p in &refined.positions {
+            assert!(p.iter().any(|&c| c.abs() < 1e-6 || (c - 1.0).abs() < 1e-6));
+        }
+        // still sharp after two levels
+        assert!(refined
+            .subdivision
+            .crease_edges
+            .iter()
+            .all(|&(_, sharpness)| sharpness == 8.0));
+        assert_eq!(refined.subdivision.crease_edges.len(), 12 * 4);
+    }
+
+    #[test]
+    fn boundary_interpolation_decides_corners() {
+        let pinned = subdivide(&grid(InterpolateBoundary::EdgeAndCorner), 1);
+        assert_eq!(pinned.positions[0], [0.0, 0.0, 0.0]);
+        assert_eq!(pinned.positions[8], [2.0, 2.0, 0.0]);
+        assert!(pinned.positions.iter().all(|p| p[2] == 0.0));
+
+        let rounded = subdivide(&grid(InterpolateBoundary::EdgeOnly), 1);
+        assert_eq!(rounded.positions[0], [0.125, 0.125, 0.0]);
+    }
+
+    #[test]
+    fn loop_splits_triangles_in_four() {
+        let tetrahedron = mesh(
+            vec![
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0],
+                [0.0, 0.0, 1.0],
+            ],
+            &[&[0, 2, 1], &[0, 1, 3], &[1, 2, 3], &[0, 3, 2]],
+            SubdivisionScheme::Loop,
+        );
+        let refined = subdivide(&tetrahedron, 1);
+        assert_eq!(refined.face_vertex_counts, vec![3; 16]);
+        assert_eq!(refined.positions.len(), 10);
+
+        // quads are refined with Catmull-Clark instead
+        let refined = subdivide(&cube(SubdivisionScheme::Loop), 1);
+        assert_eq!(refined.face_vertex_counts, vec![4; 24]);
+    }
+
+    #[test]
+    fn primvars_follow_the_faces() {
+        fn primvar<T>(values: Vec<T>, interpolation: PrimvarInterpolation) -> Primvar<T> {
+            Primvar {
+                values,
+                indices: None,
+                interpolation,
+                element_size: 1,
+                type_name: String::new(),
+            }
+        }
+        let mut quad = mesh(
+            vec![
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 1.0, 0.0],
+                [0.0, 1.0, 0.0],
+            ],
+            &[&[0, 1, 2, 3]],
+            SubdivisionScheme::Bilinear,
+        );
+        quad.primvars = HashMap::from([
+            (
+                "st".to_string(),
+                PrimvarData::Float2(primvar(
+                    vec![[0.0, 0.0], [2.0, 0.0], [2.0, 2.0], [0.0, 2.0]],
+                    PrimvarInterpolation::FaceVarying,
+                )),
+            ),
+            (
+                "id".to_string(),
+                PrimvarData::Int(primvar(vec![7], PrimvarInterpolation::Uniform)),
+            ),
+            (
+                "normals".to_string(),
+                PrimvarData::Float3(primvar(
+                    vec![[0.0, 0.0, 1.0]; 4],
+                    PrimvarInterpolation::Vertex,
+                )),
+            ),
+        ]);
+
+        let refined = subdivide(&quad, 1);
+        assert_eq!(refined.face_vertex_counts, vec![4; 4]);
+        assert!(!refined.primvars.contains_key("normals"));
+        let st = refined.primvars["st"].as_float2().unwrap();
+        assert_eq!(st.values.len(), 16);
+        assert_eq!(
+            &st.values[..4],
+            &[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]
+        );
+        let PrimvarData::Int(id) = &refined.primvars["id"] else {
+            panic!("id is no longer an int primvar");
+        };
+        assert_eq!(id.values, vec![7; 4]);
+    }
+}
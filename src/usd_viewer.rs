@@ -1,9 +1,22 @@
 // vim: set filetype=rust:
 //! A simple 3D scene with light shining over a cube sitting on a plane.
 
-use crate::usdish::meshdata_to_bevy;
+use crate::app::{CameraList, PlaybackCommand, PlaybackState, SelectCamera};
+use std::collections::{HashMap, HashSet};
 
-use crate::open_rs_loader::{fetch_stage_usd, MeshInstance};
+use crate::curves::{curves_to_mesh, point_colors, point_radii, points_to_mesh, CurveTessellation};
+use crate::gprims::{self, Axis};
+
+use crate::usdish::{
+    apply_material_textures, camera_projection, display_material, fit_vertical_fov, light_to_bevy,
+    materialdata_to_bevy, meshdata_to_bevy, try_meshdata_to_bevy, uniform_environment_map,
+    BadFacePolicy, BevyLight, MeshConversionOptions, STAGE_LIGHTS_EXPOSURE,
+};
+
+use crate::open_rs_loader::{
+    fetch_stage_usd_with, CameraData, InstanceTransform, LoadOptions, MaterialData, MeshData,
+    StageMetadata, TransformTrack,
+};
 
 use bevy::asset::AssetMetaCheck;
 use bevy::prelude::*;
@@ -13,17 +26,25 @@ use leptos_bevy_canvas::prelude::*;
 use bevy::{
     pbr::{CascadeShadowConfigBuilder, DirectionalLightShadowMap},
     prelude::*,
-    render::mesh::MeshTag,
+    render::{camera::Exposure, mesh::MeshTag},
 };
 use bevy_panorbit_camera::{PanOrbitCamera, PanOrbitCameraPlugin};
 
 const USD_STAGE_PATH: &str = "C:/Users/Nicol/CGI/year5/slay/usd/helmet_bus_3.usdc";
 
+/// Refinement level of subdivision surfaces, `0` draws their control cages.
+const SUBDIVISION_LEVEL: u32 = 1;
+
 pub const RENDER_WIDTH: f32 = 600.0;
 pub const RENDER_HEIGHT: f32 = 500.0;
 
 #[cfg(target_arch = "wasm32")]
-pub fn usd_viewer() -> App {
+pub fn usd_viewer(
+    playback_commands: BevyEventReceiver<PlaybackCommand>,
+    playback_state: BevyEventSender<PlaybackState>,
+    camera_commands: BevyEventReceiver<SelectCamera>,
+    camera_list: BevyEventSender<CameraList>,
+) -> App {
     let mut app = App::new();
     app.add_plugins((DefaultPlugins
         .set(AssetPlugin {
@@ -42,15 +63,442 @@ pub fn usd_viewer() -> App {
         }),))
         .add_plugins(PanOrbitCameraPlugin)
         .insert_resource(DirectionalLightShadowMap { size: 8192 })
-        .add_systems(Startup, setup);
+        .init_resource::<PlaybackClock>()
+        .init_resource::<TransformTracks>()
+        .init_resource::<ViewCameras>()
+        .add_systems(Startup, setup)
+        .import_event_from_leptos(playback_commands)
+        .export_event_to_leptos(playback_state)
+        .import_event_from_leptos(camera_commands)
+        .export_event_to_leptos(camera_list)
+        .add_systems(
+            Update,
+            (
+                handle_playback_commands,
+                advance_playback,
+                apply_transform_tracks,
+                report_playback_state,
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            (
+                switch_camera,
+                report_cameras,
+                fit_film_gates,
+                toggle_point_style,
+                face_active_camera,
+            )
+                .chain(),
+        );
     app
 }
 
+/// Current position on the stage timeline.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct PlaybackClock {
+    pub time_code: f64,
+    pub start_time_code: f64,
+    pub end_time_code: f64,
+    pub time_codes_per_second: f64,
+    /// Multiplier on real time, `1.0` plays at the stage frame rate.
+    pub rate: f64,
+    pub playing: bool,
+    pub looping: bool,
+}
+
+impl Default for PlaybackClock {
+    fn default() -> Self {
+        Self::from_metadata(&StageMetadata::default())
+    }
+}
+
+impl PlaybackClock {
+    pub fn from_metadata(metadata: &StageMetadata) -> Self {
+        let start = metadata.start_time_code.unwrap_or(0.0);
+        let end = metadata.end_time_code.unwrap_or(start).max(start);
+        Self {
+            time_code: start,
+            start_time_code: start,
+            end_time_code: end,
+            time_codes_per_second: metadata.time_codes_per_second,
+            rate: 1.0,
+            playing: end > start,
+            looping: true,
+        }
+    }
+
+    /// Jump to `time_code`, clamped into the playback range.
+    pub fn seek(&mut self, time_code: f64) {
+        self.time_code = time_code.clamp(self.start_time_code, self.end_time_code);
+    }
+
+    /// Move forward by `delta` time codes, wrapping when looping and
+    /// stopping at the end otherwise.
+    pub fn advance(&mut self, delta: f64) {
+        let length = self.end_time_code - self.start_time_code;
+        let next = self.time_code + delta;
+        if length <= 0.0 {
+            self.time_code = self.start_time_code;
+        } else if self.looping {
+            self.time_code =
+                self.start_time_code + (next - self.start_time_code).rem_euclid(length);
+        } else {
+            if next >= self.end_time_code {
+                self.playing = false;
+            }
+            self.seek(next);
+        }
+    }
+}
+
+/// Animated world transforms loaded from the stage.
+#[derive(Resource, Debug, Default)]
+struct TransformTracks(Vec<TransformTrack>);
+
+/// Marks an entity whose transform follows a [`TransformTrack`].
+#[derive(Component, Debug, Clone, Copy)]
+struct AnimatedTransform {
+    track: usize,
+}
+
+/// Cameras and lights drop the stage unit scale, which would also scale
+/// their view or falloff.
+#[derive(Component, Debug, Clone, Copy)]
+struct Unscaled;
+
+/// Cameras the viewer can look through, the free orbit camera first.
+#[derive(Resource, Debug, Default)]
+struct ViewCameras {
+    entities: Vec<Entity>,
+    /// Shown in the page's camera picker.
+    names: Vec<String>,
+    active: usize,
+}
+
+/// Lens of a stage camera, refitted to the viewport as it is resized.
+#[derive(Component, Debug, Clone)]
+struct StageLens(CameraData);
+
+/// Keys picking a camera directly, `0` is the orbit camera.
+const CAMERA_KEYS: [KeyCode; 10] = [
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+/// `C` cycles through the stage cameras, digits or the page's picker jump
+/// to one.
+fn switch_camera(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut selections: EventReader<SelectCamera>,
+    mut view: ResMut<ViewCameras>,
+    mut cameras: Query<(&mut Camera, Option<&mut PanOrbitCamera>)>,
+) {
+    let count = view.entities.len();
+    let mut next = view.active;
+    if keys.just_pressed(KeyCode::KeyC) && count > 0 {
+        next = (next + 1) % count;
+    }
+    let picked = CAMERA_KEYS.iter().position(|&key| keys.just_pressed(key));
+    for index in picked
+        .into_iter()
+        .chain(selections.read().map(|select| select.0))
+    {
+        if index < count {
+            next = index;
+        }
+    }
+    if next == view.active {
+        return;
+    }
+
+    for (index, &entity) in view.entities.iter().enumerate() {
+        if let Ok((mut camera, orbit)) = cameras.get_mut(entity) {
+            camera.is_active = index == next;
+            if let Some(mut orbit) = orbit {
+                orbit.enabled = index == next;
+            }
+        }
+    }
+    view.active = next;
+}
+
+fn report_cameras(
+    view: Res<ViewCameras>,
+    mut lists: EventWriter<CameraList>,
+    mut last: Local<Option<CameraList>>,
+) {
+    let list = CameraList {
+        names: view.names.clone(),
+        active: view.active,
+    };
+    if last.as_ref() != Some(&list) {
+        lists.write(list.clone());
+        *last = Some(list);
+    }
+}
+
+/// Keep the whole authored film gate in view, as Bevy replaces the lens
+/// aspect ratio with the viewport's.
+fn fit_film_gates(mut cameras: Query<(&Camera, &StageLens, &mut Projection)>) {
+    for (camera, StageLens(lens), mut projection) in &mut cameras {
+        let Some(size) = camera.logical_viewport_size() else {
+            continue;
+        };
+        let fov = fit_vertical_fov(lens, size.x / size.y);
+        let Projection::Perspective(perspective) = projection.bypass_change_detection() else {
+            continue;
+        };
+        if perspective.fov != fov {
+            perspective.fov = fov;
+            projection.set_changed();
+        }
+    }
+}
+
+/// `Points` prims with more points than this are merged into one mesh of
+/// coarse spheres instead of spawning an entity per point.
+const MAX_POINT_ENTITIES: usize = 1024;
+
+/// One point of a `Points` prim, drawn with the current [`PointStyle`].
+#[derive(Component, Debug, Clone, Copy)]
+struct PointSprite;
+
+/// Meshes points are drawn with; `P` switches between them.
+#[derive(Resource, Debug, Clone)]
+struct PointStyle {
+    quads: bool,
+    sphere: Handle<Mesh>,
+    quad: Handle<Mesh>,
+}
+
+fn toggle_point_style(
+    keys: Res<ButtonInput<KeyCode>>,
+    style: Option<ResMut<PointStyle>>,
+    mut sprites: Query<(&mut Mesh3d, &mut Transform), With<PointSprite>>,
+) {
+    let Some(mut style) = style else {
+        return;
+    };
+    if !keys.just_pressed(KeyCode::KeyP) {
+        return;
+    }
+
+    style.quads = !style.quads;
+    let mesh = if style.quads {
+        style.quad.clone()
+    } else {
+        style.sphere.clone()
+    };
+    for (mut mesh3d, mut transform) in &mut sprites {
+        mesh3d.0 = mesh.clone();
+        transform.rotation = Quat::IDENTITY;
+    }
+}
+
+/// Turn point quads towards the camera being looked through.
+fn face_active_camera(
+    style: Option<Res<PointStyle>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    parents: Query<&GlobalTransform, Without<PointSprite>>,
+    mut sprites: Query<(&ChildOf, &mut Transform), With<PointSprite>>,
+) {
+    if !style.is_some_and(|style| style.quads) {
+        return;
+    }
+    let Some((_, camera)) = cameras.iter().find(|(camera, _)| camera.is_active) else {
+        return;
+    };
+    let (_, camera_rotation, _) = camera.to_scale_rotation_translation();
+
+    for (child_of, mut transform) in &mut sprites {
+        let parent_rotation = parents
+            .get(child_of.parent())
+            .map_or(Quat::IDENTITY, |parent| {
+                parent.to_scale_rotation_translation().1
+            });
+        transform.rotation = parent_rotation.inverse() * camera_rotation;
+    }
+}
+
+/// Geometry without a bound material, each gets a material of its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum DisplayKey {
+    Mesh(usize),
+    Curves(usize),
+    Points(usize),
+    /// A point `displayColor`, quantized to 8 bits per channel.
+    Color([u8; 3]),
+}
+
+/// StandardMaterials shared between entities: one per bound material and
+/// sidedness, and one per [`DisplayKey`].
+#[derive(Default)]
+struct MaterialCache {
+    bound: HashMap<(usize, bool), Handle<StandardMaterial>>,
+    display: HashMap<DisplayKey, Handle<StandardMaterial>>,
+}
+
+impl MaterialCache {
+    /// Material bound at `index`, `None` when there is none.
+    fn bound(
+        &mut self,
+        scene_materials: &[MaterialData],
+        index: Option<usize>,
+        double_sided: bool,
+        materials: &mut Assets<StandardMaterial>,
+        images: &mut Assets<Image>,
+    ) -> Option<Handle<StandardMaterial>> {
+        let index = index?;
+        let material = scene_materials.get(index)?;
+        let handle = self.bound.entry((index, double_sided)).or_insert_with(|| {
+            let mut standard = materialdata_to_bevy(material, double_sided);
+            for err in apply_material_textures(material, &mut standard, images) {
+                warn!("{}: {err}", material.path);
+            }
+            materials.add(standard)
+        });
+        Some(handle.clone())
+    }
+
+    fn display(
+        &mut self,
+        key: DisplayKey,
+        materials: &mut Assets<StandardMaterial>,
+        make: impl FnOnce() -> StandardMaterial,
+    ) -> Handle<StandardMaterial> {
+        self.display
+            .entry(key)
+            .or_insert_with(|| materials.add(make()))
+            .clone()
+    }
+}
+
+fn handle_playback_commands(
+    mut commands: EventReader<PlaybackCommand>,
+    mut clock: ResMut<PlaybackClock>,
+) {
+    for command in commands.read() {
+        match *command {
+            PlaybackCommand::Play => {
+                if !clock.looping && clock.time_code >= clock.end_time_code {
+                    let start = clock.start_time_code;
+                    clock.seek(start);
+                }
+                clock.playing = true;
+            }
+            PlaybackCommand::Pause => clock.playing = false,
+            PlaybackCommand::Seek(time_code) => clock.seek(time_code),
+        }
+    }
+}
+
+fn advance_playback(time: Res<Time>, mut clock: ResMut<PlaybackClock>) {
+    if !clock.playing {
+        return;
+    }
+
+    let delta = time.delta_secs_f64() * clock.time_codes_per_second * clock.rate;
+    clock.advance(delta);
+}
+
+/// Tell the page about the current frame, only when it visibly changes.
+fn report_playback_state(
+    clock: Res<PlaybackClock>,
+    mut states: EventWriter<PlaybackState>,
+    mut last: Local<Option<PlaybackState>>,
+) {
+    let state = PlaybackState {
+        time_code: clock.time_code.floor(),
+        start_time_code: clock.start_time_code,
+        end_time_code: clock.end_time_code,
+        playing: clock.playing,
+    };
+    if *last != Some(state) {
+        states.write(state);
+        *last = Some(state);
+    }
+}
+
+fn apply_transform_tracks(
+    clock: Res<PlaybackClock>,
+    tracks: Res<TransformTracks>,
+    mut animated: Query<(&AnimatedTransform, &mut Transform, Has<Unscaled>)>,
+) {
+    if !clock.is_changed() {
+        return;
+    }
+
+    for (animated, mut transform, unscaled) in &mut animated {
+        if let Some(track) = tracks.0.get(animated.track) {
+            *transform = sample_track(track, clock.time_code);
+            if unscaled {
+                transform.scale = Vec3::ONE;
+            }
+        }
+    }
+}
+
+/// Interpolate a track at `time_code`, holding the first and last samples.
+fn sample_track(track: &TransformTrack, time_code: f64) -> Transform {
+    let next = track.times.partition_point(|&t| t <= time_code);
+    let (Some(first), Some(last)) = (track.transforms.first(), track.transforms.last()) else {
+        return Transform::IDENTITY;
+    };
+    if next == 0 {
+        return matrix_to_transform(first);
+    }
+    if next >= track.transforms.len() {
+        return matrix_to_transform(last);
+    }
+
+    let (t0, t1) = (track.times[next - 1], track.times[next]);
+    let factor = ((time_code - t0) / (t1 - t0)) as f32;
+    let a = matrix_to_transform(&track.transforms[next - 1]);
+    let b = matrix_to_transform(&track.transforms[next]);
+    Transform {
+        translation: a.translation.lerp(b.translation, factor),
+        rotation: a.rotation.slerp(b.rotation, factor),
+        scale: a.scale.lerp(b.scale, factor),
+    }
+}
+
+/// Copy of the mesh behind `handle` without vertex colors, made once per mesh.
+fn uncolored_mesh(
+    meshes: &mut Assets<Mesh>,
+    uncolored: &mut HashMap<AssetId<Mesh>, Handle<Mesh>>,
+    handle: &Handle<Mesh>,
+) -> Handle<Mesh> {
+    if let Some(stripped) = uncolored.get(&handle.id()) {
+        return stripped.clone();
+    }
+    let stripped = match meshes.get(handle) {
+        Some(mesh) if mesh.contains_attribute(Mesh::ATTRIBUTE_COLOR) => {
+            let mut mesh = mesh.clone();
+            mesh.remove_attribute(Mesh::ATTRIBUTE_COLOR);
+            meshes.add(mesh)
+        }
+        _ => handle.clone(),
+    };
+    uncolored.insert(handle.id(), stripped.clone());
+    stripped
+}
+
 /// set up a simple 3D scene
 fn setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
 ) {
     // circular base
     commands.spawn((
@@ -60,46 +508,347 @@ fn setup(
     ));
 
     // import USD data without baking transforms into vertex data
-    let scene = fetch_stage_usd(USD_STAGE_PATH);
+    let options = LoadOptions {
+        dedup_meshes: true,
+        ..default()
+    };
+    let scene = match fetch_stage_usd_with(USD_STAGE_PATH, &options) {
+        Ok(scene) => scene,
+        Err(err) => {
+            error!("{err}");
+            default()
+        }
+    };
+    if let Some(report) = &scene.dedup {
+        info!("{report}");
+    }
+    // stages without metersPerUnit count as centimetres and shrink a hundredfold
+    if options.correct_units_and_axis && scene.metadata.meters_per_unit != 1.0 {
+        info!(
+            "scaling {USD_STAGE_PATH} by metersPerUnit = {} to meters",
+            scene.metadata.meters_per_unit
+        );
+    }
+    for diagnostic in &scene.diagnostics {
+        warn!("skipped while loading {USD_STAGE_PATH}: {diagnostic}");
+    }
 
-    // cache Mesh handles so instances can reuse geometry
-    let mesh_handles: Vec<Handle<Mesh>> = scene
-        .meshes
+    // cache Mesh handles so instances can reuse geometry; broken meshes are left out
+    let conversion = MeshConversionOptions {
+        bad_faces: BadFacePolicy::Skip,
+        subdivision_level: SUBDIVISION_LEVEL,
+        ..default()
+    };
+    // tangents are only worth generating for meshes drawn with a normal map
+    let normal_mapped = |material_index: Option<usize>| {
+        material_index
+            .and_then(|index| scene.materials.get(index))
+            .is_some_and(|material| material.textures.normal.is_some())
+    };
+    let tangent_meshes: HashSet<usize> = scene
+        .instances
         .iter()
-        .map(|mesh| meshes.add(meshdata_to_bevy(mesh)))
+        .filter(|instance| normal_mapped(instance.material_index))
+        .map(|instance| instance.mesh_index)
         .collect();
-
-    let material_handles: Vec<Handle<StandardMaterial>> = scene
+    let mesh_handles: Vec<Option<Handle<Mesh>>> = scene
         .meshes
         .iter()
-        .map(|mesh| {
-            let mut material = StandardMaterial::from(Color::srgb(0.7, 0.4, 1.0));
-            material.double_sided = mesh.double_sided;
-
-            // ✅ Ensure culling is disabled when double-sided
-            if mesh.double_sided {
-                material.cull_mode = None;
+        .enumerate()
+        .map(|(index, mesh)| {
+            let options = MeshConversionOptions {
+                generate_tangents: tangent_meshes.contains(&index),
+                ..conversion.clone()
+            };
+            match try_meshdata_to_bevy(mesh, &options) {
+                Ok(mesh) => Some(meshes.add(mesh)),
+                Err(err) => {
+                    warn!("skipping mesh {index}: {err}");
+                    None
+                }
             }
-
-            materials.add(material)
         })
         .collect();
 
+    // unbound geometry gets a material from its displayColor, bound geometry
+    // drops the vertex colors so they don't tint its material
+    let mut cache = MaterialCache::default();
+    let mut material_handle =
+        |material_index: Option<usize>, key: DisplayKey, mesh: &MeshData| match cache.bound(
+            &scene.materials,
+            material_index,
+            mesh.double_sided,
+            &mut materials,
+            &mut images,
+        ) {
+            Some(handle) => (handle, true),
+            None => (
+                cache.display(key, &mut materials, || display_material(mesh)),
+                false,
+            ),
+        };
+    let mut uncolored: HashMap<AssetId<Mesh>, Handle<Mesh>> = HashMap::new();
+
+    let clock = PlaybackClock::from_metadata(&scene.metadata);
     for instance in &scene.instances {
-        if let (Some(mesh_handle), Some(material_handle)) = (
-            mesh_handles.get(instance.mesh_index),
-            material_handles.get(instance.mesh_index),
+        if let (Some(mesh_handle), Some(mesh)) = (
+            mesh_handles
+                .get(instance.mesh_index)
+                .and_then(Option::as_ref),
+            scene.meshes.get(instance.mesh_index),
         ) {
-            commands.spawn((
-                Mesh3d(mesh_handle.clone()),
-                MeshMaterial3d(material_handle.clone()),
+            let (material, bound) = material_handle(
+                instance.material_index,
+                DisplayKey::Mesh(instance.mesh_index),
+                mesh,
+            );
+            let mesh_handle = if bound {
+                uncolored_mesh(&mut meshes, &mut uncolored, mesh_handle)
+            } else {
+                mesh_handle.clone()
+            };
+            let mut entity = commands.spawn((
+                Mesh3d(mesh_handle),
+                MeshMaterial3d(material),
                 MeshTag(instance.mesh_index as u32),
-                instance_to_transform(instance),
             ));
+            match instance.transform {
+                InstanceTransform::Static(ref matrix) => {
+                    entity.insert(matrix_to_transform(matrix));
+                }
+                InstanceTransform::Animated(track) => {
+                    let transform = scene
+                        .transform_tracks
+                        .get(track)
+                        .map(|track| sample_track(track, clock.time_code))
+                        .unwrap_or_default();
+                    entity.insert((transform, AnimatedTransform { track }));
+                }
+            }
         }
     }
 
-    // light
+    // cameras and lights start from the same kind of transform as instances
+    let placement = |transform: &InstanceTransform| match *transform {
+        InstanceTransform::Static(ref matrix) => (matrix_to_transform(matrix), None),
+        InstanceTransform::Animated(track) => (
+            scene
+                .transform_tracks
+                .get(track)
+                .map(|track| sample_track(track, clock.time_code))
+                .unwrap_or_default(),
+            Some(AnimatedTransform { track }),
+        ),
+    };
+    // sizes and clipping are converted to meters like the rest of the scene
+    let units_to_meters = scene.metadata.meters_per_unit as f32;
+
+    // curves become tube or ribbon meshes, one entity per prim
+    let tessellation = CurveTessellation::default();
+    for (index, curves) in scene.curves.iter().enumerate() {
+        let mesh = curves_to_mesh(curves, &tessellation);
+        let options = MeshConversionOptions {
+            generate_tangents: normal_mapped(curves.material_index),
+            ..conversion.clone()
+        };
+        let mut converted = match try_meshdata_to_bevy(&mesh, &options) {
+            Ok(converted) => converted,
+            Err(err) => {
+                warn!("skipping curves {}: {err}", curves.path);
+                continue;
+            }
+        };
+        let (material, bound) =
+            material_handle(curves.material_index, DisplayKey::Curves(index), &mesh);
+        if bound {
+            converted.remove_attribute(Mesh::ATTRIBUTE_COLOR);
+        }
+        let (transform, animated) = placement(&curves.transform);
+        let mut entity = commands.spawn((
+            Name::new(curves.path.clone()),
+            Mesh3d(meshes.add(converted)),
+            MeshMaterial3d(material),
+            transform,
+        ));
+        if let Some(animated) = animated {
+            entity.insert(animated);
+        }
+    }
+
+    // points share a unit sphere or quad, scaled to their width
+    let sphere = gprims::sphere(1.0, 12);
+    let coarse_sphere = gprims::sphere(1.0, 6);
+    let style = PointStyle {
+        quads: false,
+        sphere: meshes.add(meshdata_to_bevy(&sphere)),
+        quad: meshes.add(meshdata_to_bevy(&gprims::plane(2.0, 2.0, Axis::Z))),
+    };
+    for (index, points) in scene.points.iter().enumerate() {
+        let bound = cache.bound(
+            &scene.materials,
+            points.material_index,
+            false,
+            &mut materials,
+            &mut images,
+        );
+        let (transform, animated) = placement(&points.transform);
+        let mut entity = commands.spawn((
+            Name::new(points.path.clone()),
+            transform,
+            Visibility::default(),
+        ));
+        if let Some(animated) = animated {
+            entity.insert(animated);
+        }
+
+        // large clouds become one mesh, colored per vertex, and stay spheres
+        if points.positions.len() > MAX_POINT_ENTITIES {
+            let mesh = points_to_mesh(points, &coarse_sphere);
+            let mut converted = meshdata_to_bevy(&mesh);
+            let material = match bound {
+                Some(material) => {
+                    converted.remove_attribute(Mesh::ATTRIBUTE_COLOR);
+                    material
+                }
+                None => cache.display(DisplayKey::Points(index), &mut materials, || {
+                    display_material(&mesh)
+                }),
+            };
+            entity.insert((Mesh3d(meshes.add(converted)), MeshMaterial3d(material)));
+            continue;
+        }
+
+        let radii = point_radii(points);
+        let colors = point_colors(points);
+        entity.with_children(|parent| {
+            for (i, (&position, &radius)) in points.positions.iter().zip(&radii).enumerate() {
+                let material = bound.clone().unwrap_or_else(|| match &colors {
+                    Some(colors) => {
+                        let [r, g, b] = colors[i];
+                        let color = Color::linear_rgb(r, g, b);
+                        let key = color.to_srgba().to_u8_array_no_alpha();
+                        cache.display(DisplayKey::Color(key), &mut materials, || color.into())
+                    }
+                    None => cache.display(DisplayKey::Points(index), &mut materials, || {
+                        display_material(&sphere)
+                    }),
+                });
+                parent.spawn((
+                    Mesh3d(style.sphere.clone()),
+                    MeshMaterial3d(material),
+                    Transform::from_translation(Vec3::from_array(position))
+                        .with_scale(Vec3::splat(radius)),
+                    PointSprite,
+                ));
+            }
+        });
+    }
+    if scene
+        .points
+        .iter()
+        .any(|points| points.positions.len() <= MAX_POINT_ENTITIES)
+    {
+        info!("press P to switch points between spheres and camera-facing quads");
+    }
+    commands.insert_resource(style);
+
+    // stage lights; domes light every camera through an environment map
+    let mut environment = None;
+    for light in &scene.lights {
+        let mut errors = Vec::new();
+        let converted = light_to_bevy(light, units_to_meters, &mut errors);
+        for err in errors {
+            warn!("{}: {err}", light.path);
+        }
+        let (transform, animated) = placement(&light.transform);
+        let mut entity = match converted {
+            BevyLight::Directional(light) => commands.spawn(light),
+            BevyLight::Point(light) => commands.spawn(light),
+            BevyLight::Spot(light) => commands.spawn(light),
+            BevyLight::Environment(radiance) => {
+                if environment.is_some() {
+                    warn!("{}: only the first dome light is used", light.path);
+                } else {
+                    environment = Some(uniform_environment_map(radiance, &mut images));
+                }
+                continue;
+            }
+        };
+        entity.insert((
+            Name::new(light.path.clone()),
+            transform.with_scale(Vec3::ONE),
+            Unscaled,
+        ));
+        if let Some(animated) = animated {
+            entity.insert(animated);
+        }
+    }
+
+    // stage lights are in nits, far dimmer than the default daylight exposure expects
+    let stage_lit = !scene.lights.is_empty();
+    let exposure = if stage_lit {
+        STAGE_LIGHTS_EXPOSURE
+    } else {
+        Exposure::default()
+    };
+
+    // orbit camera, active until a stage camera is picked
+    let mut orbit = commands.spawn((
+        Transform::from_xyz(-2.5, 4.5, 9.0).looking_at(Vec3::ZERO, Vec3::Y),
+        PanOrbitCamera::default(),
+        exposure,
+    ));
+    if let Some(environment) = &environment {
+        orbit.insert(environment.clone());
+    }
+    let mut view_cameras = vec![orbit.id()];
+    let mut camera_names = vec!["Orbit".to_string()];
+
+    // stage cameras
+    for stage_camera in &scene.cameras {
+        let (transform, animated) = placement(&stage_camera.transform);
+        let mut entity = commands.spawn((
+            Name::new(stage_camera.path.clone()),
+            Camera3d::default(),
+            Camera {
+                is_active: false,
+                ..default()
+            },
+            camera_projection(stage_camera, units_to_meters),
+            StageLens(stage_camera.clone()),
+            exposure,
+            transform.with_scale(Vec3::ONE),
+            Unscaled,
+        ));
+        if let Some(environment) = &environment {
+            entity.insert(environment.clone());
+        }
+        if let Some(animated) = animated {
+            entity.insert(animated);
+        }
+        view_cameras.push(entity.id());
+        camera_names.push(stage_camera.path.clone());
+    }
+    if view_cameras.len() > 1 {
+        info!(
+            "{} stage cameras, pick one below the canvas or press C or 1-9, 0 orbits",
+            view_cameras.len() - 1
+        );
+    }
+    commands.insert_resource(ViewCameras {
+        entities: view_cameras,
+        names: camera_names,
+        active: 0,
+    });
+
+    // the default sun and sky only light stages without lights of their own
+    commands.insert_resource(clock);
+    commands.insert_resource(TransformTracks(scene.transform_tracks));
+    if stage_lit {
+        commands.insert_resource(AmbientLight::NONE);
+        return;
+    }
+
     // directional sun
 
     use bevy::math::EulerRot;
@@ -134,33 +883,99 @@ fn setup(
         brightness: 200.0,
         affects_lightmapped_meshes: true,
     });
-
-    // camera
-    commands.spawn((
-        Transform::from_xyz(-2.5, 4.5, 9.0).looking_at(Vec3::ZERO, Vec3::Y),
-        PanOrbitCamera::default(),
-    ));
 }
 
-fn instance_to_transform(instance: &MeshInstance) -> Transform {
+fn matrix_to_transform(matrix: &[[f32; 4]; 4]) -> Transform {
     let mat = Mat4::from_cols_array(&[
-        instance.transform[0][0],
-        instance.transform[1][0],
-        instance.transform[2][0],
-        instance.transform[3][0],
-        instance.transform[0][1],
-        instance.transform[1][1],
-        instance.transform[2][1],
-        instance.transform[3][1],
-        instance.transform[0][2],
-        instance.transform[1][2],
-        instance.transform[2][2],
-        instance.transform[3][2],
-        instance.transform[0][3],
-        instance.transform[1][3],
-        instance.transform[2][3],
-        instance.transform[3][3],
+        matrix[0][0],
+        matrix[1][0],
+        matrix[2][0],
+        matrix[3][0],
+        matrix[0][1],
+        matrix[1][1],
+        matrix[2][1],
+        matrix[3][1],
+        matrix[0][2],
+        matrix[1][2],
+        matrix[2][2],
+        matrix[3][2],
+        matrix[0][3],
+        matrix[1][3],
+        matrix[2][3],
+        matrix[3][3],
     ]);
 
     Transform::from_matrix(mat)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock(looping: bool) -> PlaybackClock {
+        let mut clock = PlaybackClock::from_metadata(&StageMetadata {
+            start_time_code: Some(1.0),
+            end_time_code: Some(11.0),
+            ..StageMetadata::default()
+        });
+        clock.looping = looping;
+        clock
+    }
+
+    #[test]
+    fn looping_playback_wraps_into_the_range() {
+        let mut clock = clock(true);
+        assert!(clock.playing);
+        clock.seek(9.0);
+        clock.advance(4.0);
+        assert_eq!(clock.time_code, 3.0);
+        clock.advance(-3.0);
+        assert_eq!(clock.time_code, 10.0);
+        assert!(clock.playing);
+    }
+
+    #[test]
+    fn playback_stops_at_the_end() {
+        let mut clock = clock(false);
+        clock.seek(-5.0);
+        assert_eq!(clock.time_code, 1.0);
+        clock.advance(4.0);
+        assert_eq!(clock.time_code, 5.0);
+        assert!(clock.playing);
+        clock.advance(20.0);
+        assert_eq!(clock.time_code, 11.0);
+        assert!(!clock.playing);
+
+        // a single frame stage never moves
+        let mut still = PlaybackClock::default();
+        assert!(!still.playing);
+        still.advance(1.0);
+        assert_eq!(still.time_code, 0.0);
+    }
+
+    fn translation(x: f32) -> [[f32; 4]; 4] {
+        let mut matrix = Mat4::IDENTITY.to_cols_array_2d();
+        matrix[0][3] = x;
+        matrix
+    }
+
+    #[test]
+    fn tracks_interpolate_between_samples() {
+        let track = TransformTrack {
+            times: vec![0.0, 10.0, 20.0],
+            transforms: vec![translation(0.0), translation(10.0), translation(30.0)],
+        };
+        let x = |time_code| sample_track(&track, time_code).translation.x;
+        assert_eq!(x(-1.0), 0.0);
+        assert!((x(2.5) - 2.5).abs() < 1e-6);
+        assert_eq!(x(10.0), 10.0);
+        assert!((x(15.0) - 20.0).abs() < 1e-5);
+        assert_eq!(x(25.0), 30.0);
+
+        let empty = TransformTrack {
+            times: Vec::new(),
+            transforms: Vec::new(),
+        };
+        assert_eq!(sample_track(&empty, 1.0), Transform::IDENTITY);
+    }
+}
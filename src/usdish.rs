@@ -1,47 +1,270 @@
 use bevy::{
+    image::{
+        CompressedImageFormats, ImageAddressMode, ImageSampler, ImageSamplerDescriptor, ImageType,
+    },
     prelude::*,
-    render::{mesh::Indices, render_asset::RenderAssetUsages, render_resource::PrimitiveTopology},
+    render::{
+        camera::{Exposure, ScalingMode},
+        mesh::{Indices, MeshVertexAttribute, VertexAttributeValues},
+        render_asset::RenderAssetUsages,
+        render_resource::{
+            Extent3d, PrimitiveTopology, TextureDimension, TextureFormat, TextureViewDescriptor,
+            TextureViewDimension, VertexFormat,
+        },
+    },
 };
 
-use crate::open_rs_loader::{MeshData, PrimvarInterpolation};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    f32::consts::{FRAC_PI_2, PI},
+    fmt,
+    path::Path,
+};
+
+use crate::open_rs_loader::{
+    CameraData, CameraProjection, LightData, LightKind, MaterialData, MeshData, Orientation,
+    Primvar, PrimvarData, PrimvarInterpolation, SourceColorSpace, SubdivisionScheme,
+    TextureChannel, TextureData, TextureWrap,
+};
+use crate::subdivision::subdivide;
+
+/// Reasons a [`MeshData`] cannot be converted into a Bevy [`Mesh`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MeshConversionError {
+    /// A face references a point that does not exist.
+    IndexOutOfRange {
+        face: usize,
+        wedge: usize,
+        index: usize,
+        vertex_count: usize,
+    },
+    /// `face_vertex_counts` does not add up to the number of face-vertex indices.
+    FaceCountMismatch { counted: usize, indices: usize },
+    /// Every face was skipped, nothing is left to draw.
+    NoValidFaces { skipped: usize },
+}
+
+impl fmt::Display for MeshConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MeshConversionError::IndexOutOfRange {
+                face,
+                wedge,
+                index,
+                vertex_count,
+            } => write!(
+                f,
+                "face {face}: face_vertex_indices[{wedge}] = {index} out of range (positions.len() = {vertex_count})"
+            ),
+            MeshConversionError::FaceCountMismatch { counted, indices } => write!(
+                f,
+                "sum(face_vertex_counts) = {counted} but face_vertex_indices.len() = {indices}"
+            ),
+            MeshConversionError::NoValidFaces { skipped } => {
+                write!(f, "all {skipped} faces were invalid")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MeshConversionError {}
+
+/// What to do with faces whose topology is broken.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BadFacePolicy {
+    /// Fail the whole mesh on the first bad face.
+    #[default]
+    Reject,
+    /// Drop bad faces and convert the rest.
+    Skip,
+}
+
+/// Crease angle of [`NormalGeneration::default`], in radians.
+pub const DEFAULT_CREASE_ANGLE: f32 = PI / 3.0;
+
+/// How normals are made for meshes that do not author them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NormalGeneration {
+    /// One normal per face.
+    Flat,
+    /// Angle weighted normals, shared across edges whose faces meet at no
+    /// more than `crease_angle` radians and that carry no crease tag.
+    Smooth { crease_angle: f32 },
+}
 
+impl Default for NormalGeneration {
+    fn default() -> Self {
+        NormalGeneration::Smooth {
+            crease_angle: DEFAULT_CREASE_ANGLE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MeshConversionOptions {
+    pub bad_faces: BadFacePolicy,
+    /// Refinement level of meshes with a subdivision scheme, `0` draws the
+    /// control cage as authored.
+    pub subdivision_level: u32,
+    pub normals: NormalGeneration,
+    /// Generate MikkTSpace tangents for meshes with texture coordinates, as
+    /// normal maps need them. Authored `primvars:tangents` are always used.
+    pub generate_tangents: bool,
+    /// Primvars uploaded as vertex attributes for custom shaders, by name.
+    /// Only vertex, varying and faceVarying primvars qualify, as constant and
+    /// uniform ones would repeat one value over many vertices. Their ids
+    /// follow from their position, see [`primvar_vertex_attribute`].
+    pub primvar_attributes: Vec<&'static str>,
+}
+
+/// Triangulate faces, returning the wedge of every triangle corner.
+///
+/// Triangles come out counter-clockwise, as Bevy expects front faces.
+/// Triangles and convex quads are split directly, everything else is ear
+/// clipped so concave faces do not fold over themselves.
 fn triangulate(
-    counts: &[usize],
-    indices: &[u32],
-    positions_ref: &[Vec3],
-    normals_ref: &[Vec3],
-    uvs_ref: &[Vec2],
-) -> (Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<[f32; 2]>, Vec<u32>) {
-    let mut new_positions = Vec::new();
-    let mut new_normals = Vec::new();
-    let mut new_uvs = Vec::new();
-    let mut tri_indices = Vec::new();
+    positions: &[Vec3],
+    face_vertex_counts: &[usize],
+    face_vertex_indices: &[usize],
+    orientation: Orientation,
+) -> Vec<usize> {
+    let mut corners = Vec::new();
 
     let mut wedge_idx = 0;
-    for &n in counts {
-        for i in 0..(n.saturating_sub(2)) {
-            let idxs = [
-                indices[wedge_idx] as usize,
-                indices[wedge_idx + i + 2] as usize,
-                indices[wedge_idx + i + 1] as usize,
-            ];
-            for &idx in &idxs {
-                tri_indices.push(new_positions.len() as u32);
-                new_positions.push(positions_ref[idx].to_array());
-                new_normals.push(normals_ref[idx].to_array());
-                new_uvs.push(uvs_ref[idx].to_array());
+    for &n in face_vertex_counts {
+        let face = &face_vertex_indices[wedge_idx..wedge_idx + n];
+        let mut emit = |[a, b, c]: [usize; 3]| {
+            let [a, b, c] = [a, b, c].map(|corner| wedge_idx + corner);
+            match orientation {
+                Orientation::RightHanded => corners.extend([a, b, c]),
+                Orientation::LeftHanded => corners.extend([a, c, b]),
+            }
+        };
+        match n {
+            0..=2 => {}
+            3 => emit([0, 1, 2]),
+            4 if is_convex_quad(positions, face) => {
+                emit([0, 1, 2]);
+                emit([0, 2, 3]);
             }
+            _ => ear_clip(positions, face).into_iter().for_each(emit),
         }
         wedge_idx += n;
     }
 
-    (new_positions, new_normals, new_uvs, tri_indices)
+    corners
+}
+
+/// Whether every corner of a quad turns the same way around its normal.
+fn is_convex_quad(positions: &[Vec3], face: &[usize]) -> bool {
+    let [a, b, c, d] = [0, 1, 2, 3].map(|i| positions[face[i]]);
+    let normal = area_normal(positions, face, Orientation::RightHanded);
+    let turn = |p: Vec3, q: Vec3, r: Vec3| (q - p).cross(r - q).dot(normal);
+    turn(a, b, c) > 0.0 && turn(b, c, d) > 0.0 && turn(c, d, a) > 0.0 && turn(d, a, b) > 0.0
+}
+
+/// Ear clipping on the face projected onto its best-fit plane.
+///
+/// Returns corners local to the face, counter-clockwise around the face's
+/// normal. Degenerate and self-intersecting faces still give `n - 2`
+/// triangles, though some may overlap.
+fn ear_clip(positions: &[Vec3], face: &[usize]) -> Vec<[usize; 3]> {
+    let n = face.len();
+    let fan = || (1..n - 1).map(|i| [0, i, i + 1]).collect();
+
+    // the vector area is the normal of the best-fit plane
+    let Some(normal) = area_normal(positions, face, Orientation::RightHanded).try_normalize()
+    else {
+        return fan();
+    };
+    let u = normal.any_orthonormal_vector();
+    let v = normal.cross(u);
+    let points: Vec<Vec2> = face
+        .iter()
+        .map(|&i| Vec2::new(positions[i].dot(u), positions[i].dot(v)))
+        .collect();
+
+    let cross = |a: Vec2, b: Vec2, c: Vec2| (b - a).perp_dot(c - a);
+    // the remaining corners form a ring linked through `prev` and `next`
+    let mut prev: Vec<usize> = (0..n).map(|i| (i + n - 1) % n).collect();
+    let mut next: Vec<usize> = (0..n).map(|i| (i + 1) % n).collect();
+    let area = |prev: &[usize], next: &[usize], i: usize| {
+        cross(points[prev[i]], points[i], points[next[i]])
+    };
+    // only reflex corners can sit inside a convex ear
+    let mut reflex: Vec<usize> = (0..n).filter(|&i| area(&prev, &next, i) <= 0.0).collect();
+    let is_ear = |prev: &[usize], next: &[usize], reflex: &[usize], i: usize| {
+        let [a, b, c] = [prev[i], i, next[i]].map(|k| points[k]);
+        cross(a, b, c) > 0.0
+            && reflex.iter().all(|&k| {
+                let p = points[k];
+                p == a
+                    || p == b
+                    || p == c
+                    || cross(a, b, p) < 0.0
+                    || cross(b, c, p) < 0.0
+                    || cross(c, a, p) < 0.0
+            })
+    };
+    let mut ears: Vec<bool> = (0..n).map(|i| is_ear(&prev, &next, &reflex, i)).collect();
+
+    let mut triangles = Vec::with_capacity(n - 2);
+    let mut current = 0;
+    for remaining in (4..=n).rev() {
+        let ring = std::iter::successors(Some(current), |&i| Some(next[i])).take(remaining);
+        // without a clean ear the face is degenerate, clip its most convex corner
+        let ear = ring.clone().find(|&i| ears[i]).unwrap_or_else(|| {
+            ring.max_by(|&i, &j| area(&prev, &next, i).total_cmp(&area(&prev, &next, j)))
+                .unwrap_or(current)
+        });
+        triangles.push([prev[ear], ear, next[ear]]);
+
+        // unlink the ear, only its two neighbours change shape
+        let (before, after) = (prev[ear], next[ear]);
+        next[before] = after;
+        prev[after] = before;
+        reflex.retain(|&k| k != ear);
+        for k in [before, after] {
+            let convex = area(&prev, &next, k) > 0.0;
+            let listed = reflex.contains(&k);
+            if convex && listed {
+                reflex.retain(|&r| r != k);
+            } else if !convex && !listed {
+                reflex.push(k);
+            }
+        }
+        for k in [before, after] {
+            ears[k] = is_ear(&prev, &next, &reflex, k);
+        }
+        current = after;
+    }
+    triangles.push([prev[current], current, next[current]]);
+    triangles
+}
+
+/// Sum of the fan triangle normals of a face, its length grows with the area.
+fn area_normal(positions: &[Vec3], face: &[usize], orientation: Orientation) -> Vec3 {
+    let p0 = positions[face[0]];
+    let mut face_normal = Vec3::ZERO;
+
+    for tri_offset in 1..(face.len() - 1) {
+        let edge1 = positions[face[tri_offset]] - p0;
+        let edge2 = positions[face[tri_offset + 1]] - p0;
+        face_normal += edge1.cross(edge2);
+    }
+
+    match orientation {
+        Orientation::RightHanded => face_normal,
+        Orientation::LeftHanded => -face_normal,
+    }
 }
 
 fn generate_wedge_normals(
     positions: &[Vec3],
     face_vertex_counts: &[usize],
     face_vertex_indices: &[usize],
+    orientation: Orientation,
 ) -> Vec<Vec3> {
     let mut wedge_normals = Vec::with_capacity(face_vertex_indices.len());
     let mut cursor = 0;
@@ -55,17 +278,8 @@ fn generate_wedge_normals(
             continue;
         }
 
-        let first_index = face_vertex_indices[cursor];
-        let p0 = positions[first_index];
-        let mut face_normal = Vec3::ZERO;
-
-        for tri_offset in 1..(count - 1) {
-            let i1 = face_vertex_indices[cursor + tri_offset];
-            let i2 = face_vertex_indices[cursor + tri_offset + 1];
-            let edge1 = positions[i1] - p0;
-            let edge2 = positions[i2] - p0;
-            face_normal += edge2.cross(edge1);
-        }
+        let face = &face_vertex_indices[cursor..cursor + count];
+        let mut face_normal = area_normal(positions, face, orientation);
 
         if face_normal.length_squared() <= f32::EPSILON {
             face_normal = Vec3::Y;
@@ -83,182 +297,2149 @@ fn generate_wedge_normals(
     wedge_normals
 }
 
-fn sample_normals(values: &[Vec3], indices: &[usize]) -> Option<Vec<Vec3>> {
-    let mut out = Vec::with_capacity(indices.len());
-    for &idx in indices {
-        let value = *values.get(idx)?;
-        out.push(value);
+/// Angle weighted normals shared by the face-vertices around each point,
+/// split where faces meet at more than `crease_angle` or along tagged creases.
+fn generate_smooth_wedge_normals(
+    positions: &[Vec3],
+    face_vertex_counts: &[usize],
+    face_vertex_indices: &[usize],
+    orientation: Orientation,
+    crease_angle: f32,
+    creases: &[([usize; 2], f32)],
+) -> Vec<Vec3> {
+    let edge_key = |a: usize, b: usize| [a.min(b), a.max(b)];
+    let hard: HashSet<[usize; 2]> = creases
+        .iter()
+        .filter(|&&(_, sharpness)| sharpness > 0.0)
+        .map(|&([a, b], _)| edge_key(a, b))
+        .collect();
+    let min_cos = crease_angle.cos();
+
+    // unit normal of every face, and the faces and wedges along every edge
+    let mut face_normals = Vec::with_capacity(face_vertex_counts.len());
+    let mut edges: HashMap<[usize; 2], Vec<(usize, [usize; 2])>> = HashMap::new();
+    let mut cursor = 0;
+    for (face, &count) in face_vertex_counts.iter().enumerate() {
+        let points = &face_vertex_indices[cursor..cursor + count];
+        let normal = (count >= 3)
+            .then(|| area_normal(positions, points, orientation).try_normalize())
+            .flatten();
+        face_normals.push(normal);
+        for i in 0..count {
+            let next = (i + 1) % count;
+            let (wa, wb) = (cursor + i, cursor + next);
+            let key = edge_key(points[i], points[next]);
+            let wedges = if points[i] == key[0] {
+                [wa, wb]
+            } else {
+                [wb, wa]
+            };
+            edges.entry(key).or_default().push((face, wedges));
+        }
+        cursor += count;
     }
-    Some(out)
+
+    // wedges around a point join across soft edges
+    let mut parent: Vec<usize> = (0..face_vertex_indices.len()).collect();
+    fn root(parent: &mut [usize], mut wedge: usize) -> usize {
+        while parent[wedge] != wedge {
+            parent[wedge] = parent[parent[wedge]];
+            wedge = parent[wedge];
+        }
+        wedge
+    }
+    for (key, sides) in &edges {
+        if hard.contains(key) {
+            continue;
+        }
+        for (i, &(f, wf)) in sides.iter().enumerate() {
+            for &(g, wg) in &sides[i + 1..] {
+                let soft = match (face_normals[f], face_normals[g]) {
+                    (Some(nf), Some(ng)) => nf.dot(ng) >= min_cos,
+                    _ => true,
+                };
+                if soft {
+                    for (a, b) in wf.into_iter().zip(wg) {
+                        let (ra, rb) = (root(&mut parent, a), root(&mut parent, b));
+                        parent[ra] = rb;
+                    }
+                }
+            }
+        }
+    }
+
+    // every face adds its normal weighted by its angle at the corner
+    let mut sums = vec![Vec3::ZERO; face_vertex_indices.len()];
+    let mut cursor = 0;
+    for (face, &count) in face_vertex_counts.iter().enumerate() {
+        if let Some(normal) = face_normals[face] {
+            let points = &face_vertex_indices[cursor..cursor + count];
+            for i in 0..count {
+                let p = positions[points[i]];
+                let prev = (positions[points[(i + count - 1) % count]] - p).try_normalize();
+                let next = (positions[points[(i + 1) % count]] - p).try_normalize();
+                if let (Some(prev), Some(next)) = (prev, next) {
+                    let angle = prev.dot(next).clamp(-1.0, 1.0).acos();
+                    let group = root(&mut parent, cursor + i);
+                    sums[group] += angle * normal;
+                }
+            }
+        }
+        cursor += count;
+    }
+
+    (0..face_vertex_indices.len())
+        .map(|wedge| {
+            sums[root(&mut parent, wedge)]
+                .try_normalize()
+                .unwrap_or(Vec3::Y)
+        })
+        .collect()
 }
 
-fn expand_normals_to_wedges(mesh: &MeshData, positions: &[Vec3], fv_idx: &[usize]) -> Vec<Vec3> {
+/// Element of a primvar read by every face-vertex, after its indices.
+///
+/// This is the one place interpolation is interpreted; `None` means the
+/// number of elements does not fit the interpolation.
+fn wedge_elements(
+    element_count: usize,
+    indices: Option<&[usize]>,
+    interpolation: PrimvarInterpolation,
+    face_vertex_counts: &[usize],
+    fv_idx: &[usize],
+    vertex_count: usize,
+) -> Option<Vec<usize>> {
     let wedge_count = fv_idx.len();
-    let vertex_count = positions.len();
-    let face_count = mesh.face_vertex_counts.len();
+    let face_count = face_vertex_counts.len();
+    let slots = indices.map_or(element_count, <[usize]>::len);
 
-    let values = match &mesh.normals {
-        Some(normals) => normals
+    let slot_of_wedge: Vec<usize> = match interpolation {
+        PrimvarInterpolation::FaceVarying if slots == wedge_count => (0..wedge_count).collect(),
+        PrimvarInterpolation::Vertex | PrimvarInterpolation::Varying if slots == vertex_count => {
+            fv_idx.to_vec()
+        }
+        // per-wedge data tagged as per-vertex is common enough to accept
+        PrimvarInterpolation::Vertex | PrimvarInterpolation::Varying if slots == wedge_count => {
+            (0..wedge_count).collect()
+        }
+        PrimvarInterpolation::Uniform if slots == face_count => face_vertex_counts
             .iter()
-            .map(|&n| Vec3::from(n))
-            .collect::<Vec<Vec3>>(),
-        None => return generate_wedge_normals(positions, &mesh.face_vertex_counts, fv_idx),
+            .enumerate()
+            .flat_map(|(face, &count)| std::iter::repeat_n(face, count))
+            .collect(),
+        PrimvarInterpolation::Constant if slots > 0 => vec![0; wedge_count],
+        _ => return None,
     };
 
-    let indices_opt = mesh.normal_indices.as_deref();
-    let interpolation = mesh
-        .normal_interpolation
-        .unwrap_or(PrimvarInterpolation::Vertex);
+    slot_of_wedge
+        .into_iter()
+        .map(|slot| {
+            let element = indices.map_or(slot, |indices| indices[slot]);
+            (element < element_count).then_some(element)
+        })
+        .collect()
+}
 
-    let result = match interpolation {
-        PrimvarInterpolation::FaceVarying => {
-            if let Some(indices) = indices_opt {
-                if indices.len() == wedge_count {
-                    sample_normals(&values, indices)
-                } else {
-                    None
+/// Expand a primvar to one value per face-vertex. Only the first value of
+/// multi-value elements is kept.
+fn expand_primvar<T: Copy>(
+    mesh: &MeshData,
+    primvar: &Primvar<T>,
+    fv_idx: &[usize],
+) -> Option<Vec<T>> {
+    let elements = wedge_elements(
+        primvar.element_count(),
+        primvar.indices.as_deref(),
+        primvar.interpolation,
+        &mesh.face_vertex_counts,
+        fv_idx,
+        mesh.positions.len(),
+    )?;
+    Some(
+        elements
+            .into_iter()
+            .map(|element| primvar.values[element * primvar.element_size])
+            .collect(),
+    )
+}
+
+fn expand_normals_to_wedges(
+    mesh: &MeshData,
+    positions: &[Vec3],
+    fv_idx: &[usize],
+    generation: NormalGeneration,
+) -> Vec<Vec3> {
+    mesh.primvars
+        .get("normals")
+        .and_then(PrimvarData::as_float3)
+        .and_then(|normals| expand_primvar(mesh, normals, fv_idx))
+        .map(|normals| normals.into_iter().map(Vec3::from).collect())
+        .unwrap_or_else(|| {
+            let counts = &mesh.face_vertex_counts;
+            match generation {
+                NormalGeneration::Flat => {
+                    generate_wedge_normals(positions, counts, fv_idx, mesh.orientation)
                 }
-            } else if values.len() == wedge_count {
-                Some(values.clone())
-            } else {
-                None
+                NormalGeneration::Smooth { crease_angle } => generate_smooth_wedge_normals(
+                    positions,
+                    counts,
+                    fv_idx,
+                    mesh.orientation,
+                    crease_angle,
+                    &mesh.subdivision.crease_edges,
+                ),
             }
-        }
-        PrimvarInterpolation::Vertex | PrimvarInterpolation::Varying => {
-            if let Some(indices) = indices_opt {
-                if indices.len() == vertex_count {
-                    sample_normals(&values, indices).map(|per_vertex| {
-                        fv_idx
-                            .iter()
-                            .map(|&v_idx| *per_vertex.get(v_idx).unwrap_or(&Vec3::Y))
-                            .collect()
-                    })
-                } else if indices.len() == wedge_count {
-                    sample_normals(&values, indices)
-                } else {
-                    None
-                }
-            } else if values.len() == vertex_count {
-                Some(
-                    fv_idx
-                        .iter()
-                        .map(|&v_idx| *values.get(v_idx).unwrap_or(&Vec3::Y))
-                        .collect(),
-                )
-            } else if values.len() == wedge_count {
-                Some(values.clone())
-            } else {
-                None
+        })
+}
+
+/// Per-wedge tangents from `primvars:tangents`, with the bitangent sign in `w`.
+///
+/// Three component tangents are taken as right-handed.
+fn expand_tangents(mesh: &MeshData, fv_idx: &[usize]) -> Option<Vec<[f32; 4]>> {
+    match mesh.primvars.get("tangents")? {
+        PrimvarData::Float4(tangents) => expand_primvar(mesh, tangents, fv_idx),
+        PrimvarData::Float3(tangents) => Some(
+            expand_primvar(mesh, tangents, fv_idx)?
+                .into_iter()
+                .map(|[x, y, z]| [x, y, z, 1.0])
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// Names of the texture coordinate primvars, `st` first.
+fn uv_sets(mesh: &MeshData) -> Vec<&str> {
+    let mut names: Vec<&str> = mesh
+        .primvars
+        .iter()
+        .filter(|(name, primvar)| {
+            primvar.as_float2().is_some()
+                && (name.as_str() == "st" || primvar.type_name().starts_with("texCoord2"))
+        })
+        .map(|(name, _)| name.as_str())
+        .collect();
+    names.sort_by_key(|&name| (name != "st", name));
+    names
+}
+
+/// Per-wedge linear RGBA from `displayColor` and `displayOpacity`.
+///
+/// `None` when neither varies over the surface; constant values tint the
+/// material instead, see [`display_material`].
+fn expand_display_colors(mesh: &MeshData, fv_idx: &[usize]) -> Option<Vec<[f32; 4]>> {
+    let varying = |interpolation| interpolation != PrimvarInterpolation::Constant;
+    let color = mesh
+        .primvars
+        .get("displayColor")
+        .and_then(PrimvarData::as_float3)
+        .filter(|primvar| varying(primvar.interpolation));
+    let opacity = mesh
+        .primvars
+        .get("displayOpacity")
+        .and_then(PrimvarData::as_float)
+        .filter(|primvar| varying(primvar.interpolation));
+    if color.is_none() && opacity.is_none() {
+        return None;
+    }
+
+    let colors = color.and_then(|primvar| expand_primvar(mesh, primvar, fv_idx));
+    let opacities = opacity.and_then(|primvar| expand_primvar(mesh, primvar, fv_idx));
+    if colors.is_none() && opacities.is_none() {
+        return None;
+    }
+
+    Some(
+        (0..fv_idx.len())
+            .map(|wedge| {
+                let [r, g, b] = colors.as_ref().map_or([1.0; 3], |c| c[wedge]);
+                let a = opacities.as_ref().map_or(1.0, |o| o[wedge]);
+                [r, g, b, a]
+            })
+            .collect(),
+    )
+}
+
+/// Check the face topology, returning the faces that can be drawn.
+///
+/// `None` means every face is fine and the mesh can be used as is.
+fn validate_faces(
+    mesh: &MeshData,
+    policy: BadFacePolicy,
+) -> Result<Option<Vec<usize>>, MeshConversionError> {
+    let vertex_count = mesh.positions.len();
+    let index_count = mesh.face_vertex_indices.len();
+    let counted: usize = mesh.face_vertex_counts.iter().sum();
+
+    let mut kept = Vec::with_capacity(mesh.face_vertex_counts.len());
+    let mut problem = None;
+    let mut cursor = 0;
+
+    for (face, &count) in mesh.face_vertex_counts.iter().enumerate() {
+        let end = cursor + count;
+        let face_problem = if end > index_count {
+            Some(MeshConversionError::FaceCountMismatch {
+                counted,
+                indices: index_count,
+            })
+        } else {
+            (cursor..end)
+                .find(|&wedge| mesh.face_vertex_indices[wedge] >= vertex_count)
+                .map(|wedge| MeshConversionError::IndexOutOfRange {
+                    face,
+                    wedge,
+                    index: mesh.face_vertex_indices[wedge],
+                    vertex_count,
+                })
+        };
+
+        match face_problem {
+            Some(err) if policy == BadFacePolicy::Reject => return Err(err),
+            Some(err) => {
+                problem.get_or_insert(err);
             }
+            None => kept.push(face),
         }
-        PrimvarInterpolation::Uniform => {
-            let per_face = if let Some(indices) = indices_opt {
-                if indices.len() == face_count {
-                    sample_normals(&values, indices)
-                } else {
-                    None
-                }
-            } else if values.len() == face_count {
-                Some(values.clone())
-            } else {
-                None
-            };
+        cursor = end;
+    }
 
-            per_face.map(|per_face_vals| {
-                let mut out = Vec::with_capacity(wedge_count);
-                for (face_idx, &count) in mesh.face_vertex_counts.iter().enumerate() {
-                    let normal = *per_face_vals.get(face_idx).unwrap_or(&Vec3::Y);
-                    for _ in 0..count {
-                        out.push(normal);
-                    }
-                }
-                out
-            })
+    if counted < index_count {
+        let err = MeshConversionError::FaceCountMismatch {
+            counted,
+            indices: index_count,
+        };
+        if policy == BadFacePolicy::Reject {
+            return Err(err);
         }
-        PrimvarInterpolation::Constant => {
-            if let Some(indices) = indices_opt {
-                if !indices.is_empty() {
-                    sample_normals(&values, &indices[..1]).map(|vals| vec![vals[0]; wedge_count])
-                } else {
-                    None
-                }
-            } else if !values.is_empty() {
-                Some(vec![values[0]; wedge_count])
-            } else {
-                None
+        problem.get_or_insert(err);
+    }
+
+    match problem {
+        None => Ok(None),
+        Some(_) if kept.is_empty() => Err(MeshConversionError::NoValidFaces {
+            skipped: mesh.face_vertex_counts.len(),
+        }),
+        Some(_) => Ok(Some(kept)),
+    }
+}
+
+/// Which wedges or faces of a primvar with `len` elements survive face removal.
+///
+/// `None` means the values are per point (or constant) and stay untouched.
+fn surviving_elements<'a>(
+    interpolation: PrimvarInterpolation,
+    len: usize,
+    vertex_count: usize,
+    kept_wedges: &'a [usize],
+    wedge_count: usize,
+    kept_faces: &'a [usize],
+    face_count: usize,
+) -> Option<&'a [usize]> {
+    match interpolation {
+        PrimvarInterpolation::FaceVarying if len == wedge_count => Some(kept_wedges),
+        PrimvarInterpolation::Uniform if len == face_count => Some(kept_faces),
+        PrimvarInterpolation::Vertex | PrimvarInterpolation::Varying
+            if len == wedge_count && len != vertex_count =>
+        {
+            Some(kept_wedges)
+        }
+        _ => None,
+    }
+}
+
+/// Copy of `mesh` restricted to the faces in `kept`.
+fn retain_faces(mesh: &MeshData, kept: &[usize]) -> MeshData {
+    let mut face_starts = Vec::with_capacity(mesh.face_vertex_counts.len());
+    let mut cursor = 0;
+    for &count in &mesh.face_vertex_counts {
+        face_starts.push(cursor);
+        cursor += count;
+    }
+
+    let vertex_count = mesh.positions.len();
+    let wedge_count = mesh.face_vertex_indices.len();
+    let face_count = mesh.face_vertex_counts.len();
+    let kept_wedges: Vec<usize> = kept
+        .iter()
+        .flat_map(|&face| face_starts[face]..face_starts[face] + mesh.face_vertex_counts[face])
+        .collect();
+
+    let mut out = mesh.clone();
+    out.face_vertex_counts = kept
+        .iter()
+        .map(|&face| mesh.face_vertex_counts[face])
+        .collect();
+    out.face_vertex_indices = kept_wedges
+        .iter()
+        .map(|&wedge| mesh.face_vertex_indices[wedge])
+        .collect();
+    out.hole_indices = kept
+        .iter()
+        .enumerate()
+        .filter(|(_, face)| mesh.hole_indices.binary_search(face).is_ok())
+        .map(|(new_face, _)| new_face)
+        .collect();
+
+    // wedge or face indexed data has to follow the faces that survive
+    let survivors = |interpolation, len| {
+        surviving_elements(
+            interpolation,
+            len,
+            vertex_count,
+            &kept_wedges,
+            wedge_count,
+            kept,
+            face_count,
+        )
+    };
+    out.primvars = mesh
+        .primvars
+        .iter()
+        .map(|(name, primvar)| (name.clone(), retain_primvar_data(primvar, survivors)))
+        .collect();
+
+    out
+}
+
+/// Copy of `primvar` keeping only the wedges or faces `survivors` reports.
+fn retain_primvar<'a, T: Copy>(
+    primvar: &Primvar<T>,
+    survivors: impl Fn(PrimvarInterpolation, usize) -> Option<&'a [usize]>,
+) -> Primvar<T> {
+    let mut out = primvar.clone();
+    match &primvar.indices {
+        Some(indices) => {
+            if let Some(which) = survivors(primvar.interpolation, indices.len()) {
+                out.indices = Some(which.iter().map(|&i| indices[i]).collect());
             }
         }
-        PrimvarInterpolation::Unknown => None,
+        None => {
+            if let Some(which) = survivors(primvar.interpolation, primvar.element_count()) {
+                let size = primvar.element_size;
+                out.values = which
+                    .iter()
+                    .flat_map(|&i| primvar.values[i * size..(i + 1) * size].iter().copied())
+                    .collect();
+            }
+        }
+    }
+    out
+}
+
+fn retain_primvar_data<'a>(
+    primvar: &PrimvarData,
+    survivors: impl Fn(PrimvarInterpolation, usize) -> Option<&'a [usize]>,
+) -> PrimvarData {
+    match primvar {
+        PrimvarData::Float(p) => PrimvarData::Float(retain_primvar(p, survivors)),
+        PrimvarData::Float2(p) => PrimvarData::Float2(retain_primvar(p, survivors)),
+        PrimvarData::Float3(p) => PrimvarData::Float3(retain_primvar(p, survivors)),
+        PrimvarData::Float4(p) => PrimvarData::Float4(retain_primvar(p, survivors)),
+        PrimvarData::Int(p) => PrimvarData::Int(retain_primvar(p, survivors)),
+    }
+}
+
+/// Convert `mesh` into a Bevy [`Mesh`], reporting broken topology instead of panicking.
+pub fn try_meshdata_to_bevy(
+    mesh: &MeshData,
+    options: &MeshConversionOptions,
+) -> Result<Mesh, MeshConversionError> {
+    let mut mesh = match validate_faces(mesh, options.bad_faces)? {
+        None => Cow::Borrowed(mesh),
+        Some(kept) => Cow::Owned(retain_faces(mesh, &kept)),
     };
 
-    result.unwrap_or_else(|| generate_wedge_normals(positions, &mesh.face_vertex_counts, fv_idx))
+    // subdivided surfaces are smooth apart from their creases, bilinear ones
+    // are shaded like polygons
+    let scheme = mesh.subdivision.scheme;
+    let normals = if options.subdivision_level > 0
+        && matches!(
+            scheme,
+            SubdivisionScheme::CatmullClark | SubdivisionScheme::Loop
+        ) {
+        NormalGeneration::Smooth { crease_angle: PI }
+    } else {
+        options.normals
+    };
+    if options.subdivision_level > 0 && scheme != SubdivisionScheme::None {
+        mesh = Cow::Owned(subdivide(&mesh, options.subdivision_level));
+    }
+
+    // holes shape the surface around them but are not drawn
+    if !mesh.hole_indices.is_empty() {
+        let solid: Vec<usize> = (0..mesh.face_vertex_counts.len())
+            .filter(|face| mesh.hole_indices.binary_search(face).is_err())
+            .collect();
+        mesh = Cow::Owned(retain_faces(&mesh, &solid));
+    }
+
+    Ok(build_bevy_mesh(&mesh, normals, options))
 }
 
+/// Convert `mesh` into a Bevy [`Mesh`].
+///
+/// # Panics
+///
+/// Panics if the topology is broken; use [`try_meshdata_to_bevy`] for untrusted data.
 pub fn meshdata_to_bevy(mesh: &MeshData) -> Mesh {
+    try_meshdata_to_bevy(mesh, &MeshConversionOptions::default())
+        .unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// Id of the first custom primvar attribute, well clear of Bevy's own.
+pub const PRIMVAR_ATTRIBUTE_ID_BASE: u64 = 0x5553_4400_0000_0000;
+
+/// Vertex attribute carrying the primvar `name` requested at `index` of
+/// [`MeshConversionOptions::primvar_attributes`].
+///
+/// Its id is [`PRIMVAR_ATTRIBUTE_ID_BASE`] plus `index`, so shaders can rely on
+/// the order the primvars were requested in.
+pub fn primvar_vertex_attribute(
+    name: &'static str,
+    index: usize,
+    format: VertexFormat,
+) -> MeshVertexAttribute {
+    MeshVertexAttribute::new(name, PRIMVAR_ATTRIBUTE_ID_BASE + index as u64, format)
+}
+
+/// Expand a custom primvar into a per-wedge vertex attribute.
+fn custom_attribute(
+    mesh: &MeshData,
+    name: &'static str,
+    index: usize,
+    primvar: &PrimvarData,
+    fv_idx: &[usize],
+) -> Option<(MeshVertexAttribute, VertexAttributeValues)> {
+    let (format, values) = match primvar {
+        PrimvarData::Float(p) => (
+            VertexFormat::Float32,
+            VertexAttributeValues::Float32(expand_primvar(mesh, p, fv_idx)?),
+        ),
+        PrimvarData::Float2(p) => (
+            VertexFormat::Float32x2,
+            VertexAttributeValues::Float32x2(expand_primvar(mesh, p, fv_idx)?),
+        ),
+        PrimvarData::Float3(p) => (
+            VertexFormat::Float32x3,
+            VertexAttributeValues::Float32x3(expand_primvar(mesh, p, fv_idx)?),
+        ),
+        PrimvarData::Float4(p) => (
+            VertexFormat::Float32x4,
+            VertexAttributeValues::Float32x4(expand_primvar(mesh, p, fv_idx)?),
+        ),
+        PrimvarData::Int(p) => (
+            VertexFormat::Sint32,
+            VertexAttributeValues::Sint32(expand_primvar(mesh, p, fv_idx)?),
+        ),
+    };
+    Some((primvar_vertex_attribute(name, index, format), values))
+}
+
+fn build_bevy_mesh(
+    mesh: &MeshData,
+    normals: NormalGeneration,
+    options: &MeshConversionOptions,
+) -> Mesh {
     // positions (vertex array)
     let positions_vtx: Vec<Vec3> = mesh.positions.iter().map(|&p| Vec3::from(p)).collect();
 
     // face indices (to vertex positions)
-    let fv_idx: Vec<usize> = mesh
-        .face_vertex_indices
-        .iter()
-        .map(|&i| i as usize)
-        .collect();
-
-    // quick sanity checks (turn into proper errors if you prefer)
-    let vtx_len = positions_vtx.len();
-    if let Some(bad) = fv_idx.iter().position(|&i| i >= vtx_len) {
-        panic!(
-            "face_vertex_indices[{}] = {} out of range (positions.len() = {})",
-            bad, fv_idx[bad], vtx_len
-        );
-    }
-    let sum_counts: usize = mesh.face_vertex_counts.iter().sum();
-    debug_assert_eq!(
-        sum_counts,
-        fv_idx.len(),
-        "sum(face_vertex_counts) != face_vertex_indices.len()"
-    );
+    let fv_idx: Vec<usize> = mesh.face_vertex_indices.clone();
 
+    // topology has been validated by `validate_faces`
     // expand to wedge-local attributes (one per face-vertex)
     let wedge_positions: Vec<Vec3> = fv_idx.iter().map(|&i| positions_vtx[i]).collect();
 
-    let wedge_normals = expand_normals_to_wedges(mesh, &positions_vtx, &fv_idx);
+    let wedge_normals = expand_normals_to_wedges(mesh, &positions_vtx, &fv_idx, normals);
 
-    let wedge_uvs: Vec<Vec2> = if let Some(uvs) = &mesh.uvs {
-        if uvs.len() == fv_idx.len() {
-            // already per-wedge
-            uvs.iter().map(|&u| Vec2::from(u)).collect()
-        } else if uvs.len() == vtx_len {
-            // per-vertex → expand
-            fv_idx.iter().map(|&i| Vec2::from(uvs[i])).collect()
-        } else {
-            // fallback
-            vec![Vec2::ZERO; wedge_positions.len()]
-        }
-    } else {
-        // no UVs at all → fallback
-        vec![Vec2::ZERO; wedge_positions.len()]
+    // the first two texture coordinate sets, missing or broken ones read as zero
+    let uv_sets = uv_sets(mesh);
+    let wedge_uvs = |set: Option<&&str>| -> Option<Vec<[f32; 2]>> {
+        let primvar = mesh.primvars.get(*set?)?.as_float2()?;
+        Some(
+            expand_primvar(mesh, primvar, &fv_idx)
+                .unwrap_or_else(|| vec![[0.0, 0.0]; fv_idx.len()]),
+        )
     };
+    let wedge_uvs_0 = wedge_uvs(uv_sets.first()).unwrap_or_else(|| vec![[0.0, 0.0]; fv_idx.len()]);
+    let wedge_uvs_1 = wedge_uvs(uv_sets.get(1));
+
+    // instances of one mesh may bind different materials, so the colors are
+    // always kept and the caller drops them where a material replaces them
+    let wedge_colors = expand_display_colors(mesh, &fv_idx);
 
-    // sequential wedge ids for triangulation fan
-    let wedge_ids: Vec<u32> = (0..wedge_positions.len() as u32).collect();
+    let wedge_tangents = expand_tangents(mesh, &fv_idx);
 
-    // triangulate using wedge-local data
-    let (flat_positions, flat_normals, flat_uvs, tri_indices) = triangulate(
+    // requested primvars not consumed above are handed to custom shaders as is
+    let custom: Vec<(MeshVertexAttribute, VertexAttributeValues)> = options
+        .primvar_attributes
+        .iter()
+        .enumerate()
+        .filter(|(_, &name)| match name {
+            "normals" | "displayColor" | "displayOpacity" => false,
+            "tangents" => wedge_tangents.is_none(),
+            _ => !uv_sets.iter().take(2).any(|&set| set == name),
+        })
+        .filter_map(|(index, &name)| {
+            let primvar = mesh.primvars.get(name)?;
+            let varying = matches!(
+                primvar.interpolation(),
+                PrimvarInterpolation::Vertex
+                    | PrimvarInterpolation::Varying
+                    | PrimvarInterpolation::FaceVarying
+            );
+            varying
+                .then(|| custom_attribute(mesh, name, index, primvar, &fv_idx))
+                .flatten()
+        })
+        .collect();
+
+    // triangulate, then weld the wedges that agree on every attribute
+    let corners = triangulate(
+        &positions_vtx,
         &mesh.face_vertex_counts,
-        &wedge_ids,
-        &wedge_positions,
-        &wedge_normals,
-        &wedge_uvs,
+        &fv_idx,
+        mesh.orientation,
     );
+    let (vertex_wedges, tri_indices) = weld_wedges(&corners, |wedge, key| {
+        let mut floats = |values: &[f32]| key.extend(values.iter().flat_map(|v| v.to_ne_bytes()));
+        floats(&wedge_positions[wedge].to_array());
+        floats(&wedge_normals[wedge].to_array());
+        floats(&wedge_uvs_0[wedge]);
+        if let Some(uvs) = &wedge_uvs_1 {
+            floats(&uvs[wedge]);
+        }
+        if let Some(colors) = &wedge_colors {
+            floats(&colors[wedge]);
+        }
+        if let Some(tangents) = &wedge_tangents {
+            floats(&tangents[wedge]);
+        }
+        for (attribute, values) in &custom {
+            let size = attribute.format.size() as usize;
+            key.extend_from_slice(&values.get_bytes()[wedge * size..(wedge + 1) * size]);
+        }
+    });
+    let gather = |wedge_values: &[Vec3]| -> Vec<[f32; 3]> {
+        vertex_wedges
+            .iter()
+            .map(|&w| wedge_values[w].to_array())
+            .collect()
+    };
+    let flat_positions = gather(&wedge_positions);
+    let flat_normals = gather(&wedge_normals);
+    let flat_uvs: Vec<[f32; 2]> = vertex_wedges.iter().map(|&w| wedge_uvs_0[w]).collect();
+
+    // 16-bit indices halve the index buffer whenever they can address every vertex
+    let indices = if vertex_wedges.len() <= usize::from(u16::MAX) + 1 {
+        Indices::U16(tri_indices.iter().map(|&i| i as u16).collect())
+    } else {
+        Indices::U32(tri_indices)
+    };
 
-    Mesh::new(
+    let mut bevy_mesh = Mesh::new(
         PrimitiveTopology::TriangleList,
         RenderAssetUsages::default(),
     )
     .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, flat_positions)
     .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, flat_normals)
     .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, flat_uvs)
-    .with_inserted_indices(Indices::U32(tri_indices))
+    .with_inserted_indices(indices);
+
+    if let Some(uvs) = wedge_uvs_1 {
+        let flat_uvs: Vec<[f32; 2]> = vertex_wedges.iter().map(|&w| uvs[w]).collect();
+        bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_UV_1, flat_uvs);
+    }
+
+    if let Some(colors) = wedge_colors {
+        let flat_colors: Vec<[f32; 4]> = vertex_wedges.iter().map(|&w| colors[w]).collect();
+        bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, flat_colors);
+    }
+
+    for (attribute, values) in custom {
+        if let Some(values) = select_wedges(&values, &vertex_wedges) {
+            bevy_mesh.insert_attribute(attribute, values);
+        }
+    }
+
+    if let Some(tangents) = wedge_tangents {
+        let flat_tangents: Vec<[f32; 4]> = vertex_wedges.iter().map(|&w| tangents[w]).collect();
+        bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_TANGENT, flat_tangents);
+    } else if options.generate_tangents && !uv_sets.is_empty() {
+        // the mesh is indexed triangles with normals and uvs, all MikkTSpace needs
+        if let Err(err) = bevy_mesh.generate_tangents() {
+            warn!("drawing without tangents, normal maps will be off: {err}");
+        }
+        // Bevy flips the bitangent for glTF's top left uv origin, but `st`
+        // starts at the bottom left and the images are flipped to match
+        if let Some(VertexAttributeValues::Float32x4(tangents)) =
+            bevy_mesh.attribute_mut(Mesh::ATTRIBUTE_TANGENT)
+        {
+            for tangent in tangents {
+                tangent[3] = -tangent[3];
+            }
+        }
+    }
+
+    bevy_mesh
+}
+
+/// Merge the wedges used by `corners` whose keys are equal.
+///
+/// `key` appends the attribute bits of a wedge. Returns the wedge every
+/// vertex is read from and the vertex of every corner.
+fn weld_wedges(corners: &[usize], key: impl Fn(usize, &mut Vec<u8>)) -> (Vec<usize>, Vec<u32>) {
+    let mut vertices: HashMap<Vec<u8>, u32> = HashMap::new();
+    let mut vertex_of_wedge: HashMap<usize, u32> = HashMap::new();
+    let mut vertex_wedges = Vec::new();
+    let mut scratch = Vec::new();
+
+    let indices = corners
+        .iter()
+        .map(|&wedge| {
+            *vertex_of_wedge.entry(wedge).or_insert_with(|| {
+                scratch.clear();
+                key(wedge, &mut scratch);
+                *vertices.entry(scratch.clone()).or_insert_with(|| {
+                    vertex_wedges.push(wedge);
+                    (vertex_wedges.len() - 1) as u32
+                })
+            })
+        })
+        .collect();
+
+    (vertex_wedges, indices)
+}
+
+/// The values read by `wedges`, in order.
+fn select_wedges(
+    values: &VertexAttributeValues,
+    wedges: &[usize],
+) -> Option<VertexAttributeValues> {
+    fn pick<T: Copy>(values: &[T], wedges: &[usize]) -> Vec<T> {
+        wedges.iter().map(|&w| values[w]).collect()
+    }
+
+    Some(match values {
+        VertexAttributeValues::Float32(v) => VertexAttributeValues::Float32(pick(v, wedges)),
+        VertexAttributeValues::Float32x2(v) => VertexAttributeValues::Float32x2(pick(v, wedges)),
+        VertexAttributeValues::Float32x3(v) => VertexAttributeValues::Float32x3(pick(v, wedges)),
+        VertexAttributeValues::Float32x4(v) => VertexAttributeValues::Float32x4(pick(v, wedges)),
+        VertexAttributeValues::Sint32(v) => VertexAttributeValues::Sint32(pick(v, wedges)),
+        _ => return None,
+    })
+}
+
+/// Build a [`StandardMaterial`] from `UsdPreviewSurface` values.
+pub fn materialdata_to_bevy(material: &MaterialData, double_sided: bool) -> StandardMaterial {
+    let [r, g, b] = material.diffuse_color;
+    let [er, eg, eb] = material.emissive_color;
+
+    let alpha_mode = if material.opacity_threshold > 0.0 {
+        AlphaMode::Mask(material.opacity_threshold)
+    } else if material.opacity < 1.0 {
+        AlphaMode::Blend
+    } else {
+        AlphaMode::Opaque
+    };
+
+    StandardMaterial {
+        base_color: Color::linear_rgba(r, g, b, material.opacity),
+        emissive: LinearRgba::rgb(er, eg, eb),
+        metallic: material.metallic,
+        perceptual_roughness: material.roughness,
+        ior: material.ior,
+        alpha_mode,
+        double_sided,
+        cull_mode: if double_sided {
+            None
+        } else {
+            StandardMaterial::default().cull_mode
+        },
+        ..default()
+    }
+}
+
+// -------- Textures --------
+
+/// A texture file that could not be turned into an image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextureLoadError {
+    /// The file could not be read.
+    Read { path: String, reason: String },
+    /// The file is not an image Bevy can decode.
+    Decode { path: String, reason: String },
+}
+
+impl fmt::Display for TextureLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextureLoadError::Read { path, reason } => {
+                write!(f, "cannot read texture {path}: {reason}")
+            }
+            TextureLoadError::Decode { path, reason } => {
+                write!(f, "cannot decode texture {path}: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TextureLoadError {}
+
+/// Linear texels of a texture after `scale` and `bias`, rows top first.
+struct TextureSamples {
+    width: u32,
+    height: u32,
+    texels: Vec<[f32; 4]>,
+}
+
+impl TextureSamples {
+    fn constant(value: [f32; 4]) -> Self {
+        Self {
+            width: 1,
+            height: 1,
+            texels: vec![value],
+        }
+    }
+
+    fn from_image(image: &Image, texture: &TextureData, srgb: bool) -> Option<Self> {
+        let rgba = image.convert(TextureFormat::Rgba8Unorm)?;
+        let texels = rgba
+            .data
+            .as_ref()?
+            .chunks_exact(4)
+            .map(|pixel| {
+                let mut texel = [0.0; 4];
+                for (c, value) in texel.iter_mut().enumerate() {
+                    let mut encoded = f32::from(pixel[c]) / 255.0;
+                    if srgb && c < 3 {
+                        encoded = Srgba::gamma_function(encoded);
+                    }
+                    *value = encoded * texture.scale[c] + texture.bias[c];
+                }
+                texel
+            })
+            .collect();
+        Some(Self {
+            width: rgba.width(),
+            height: rgba.height(),
+            texels,
+        })
+    }
+
+    /// Nearest texel to pixel `(x, y)` of an image `width` by `height`.
+    fn at(&self, x: u32, y: u32, width: u32, height: u32) -> [f32; 4] {
+        let sx = u64::from(x) * u64::from(self.width) / u64::from(width);
+        let sy = u64::from(y) * u64::from(self.height) / u64::from(height);
+        self.texels[(sy * u64::from(self.width) + sx) as usize]
+    }
+}
+
+type LoadedTexture<'a> = (&'a TextureData, TextureSamples);
+
+fn is_srgb(texture: &TextureData, color_input: bool) -> bool {
+    match texture.color_space {
+        SourceColorSpace::Srgb => true,
+        SourceColorSpace::Raw => false,
+        SourceColorSpace::Auto => color_input,
+    }
+}
+
+fn decode_texture(texture: &TextureData, srgb: bool) -> Result<TextureSamples, TextureLoadError> {
+    let bytes = std::fs::read(&texture.file).map_err(|err| TextureLoadError::Read {
+        path: texture.file.clone(),
+        reason: err.to_string(),
+    })?;
+    let decode_error = |reason: String| TextureLoadError::Decode {
+        path: texture.file.clone(),
+        reason,
+    };
+    let extension = Path::new(&texture.file)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default();
+    let image = Image::from_buffer(
+        &bytes,
+        ImageType::Extension(extension),
+        CompressedImageFormats::NONE,
+        false,
+        ImageSampler::Default,
+        RenderAssetUsages::default(),
+    )
+    .map_err(|err| decode_error(err.to_string()))?;
+    TextureSamples::from_image(&image, texture, srgb)
+        .ok_or_else(|| decode_error("unsupported pixel format".to_string()))
+}
+
+/// Reads a texture, using its `fallback` when the file is unusable.
+fn read_texture<'a>(
+    texture: Option<&'a TextureData>,
+    color_input: bool,
+    errors: &mut Vec<TextureLoadError>,
+) -> Option<LoadedTexture<'a>> {
+    let texture = texture?;
+    let samples = decode_texture(texture, is_srgb(texture, color_input)).unwrap_or_else(|err| {
+        errors.push(err);
+        TextureSamples::constant(texture.fallback)
+    });
+    Some((texture, samples))
+}
+
+fn channel_rgb(texel: [f32; 4], channel: TextureChannel) -> [f32; 3] {
+    match channel {
+        TextureChannel::Rgb => [texel[0], texel[1], texel[2]],
+        TextureChannel::R => [texel[0]; 3],
+        TextureChannel::G => [texel[1]; 3],
+        TextureChannel::B => [texel[2]; 3],
+        TextureChannel::A => [texel[3]; 3],
+    }
+}
+
+fn channel_scalar(texel: [f32; 4], channel: TextureChannel) -> f32 {
+    match channel {
+        TextureChannel::R | TextureChannel::Rgb => texel[0],
+        TextureChannel::G => texel[1],
+        TextureChannel::B => texel[2],
+        TextureChannel::A => texel[3],
+    }
+}
+
+fn address_mode(wrap: TextureWrap) -> ImageAddressMode {
+    match wrap {
+        TextureWrap::Repeat => ImageAddressMode::Repeat,
+        TextureWrap::Mirror => ImageAddressMode::MirrorRepeat,
+        // WebGL has no border color, so black borders clamp as well
+        TextureWrap::Clamp | TextureWrap::Black | TextureWrap::UseMetadata => {
+            ImageAddressMode::ClampToEdge
+        }
+    }
+}
+
+/// Size and sampler of an image packed from `textures`, the largest input wins.
+fn packed_layout(textures: &[Option<&LoadedTexture>]) -> Option<((u32, u32), ImageSampler)> {
+    let present: Vec<&LoadedTexture> = textures.iter().flatten().copied().collect();
+    let (first, _) = present.first()?;
+    let width = present.iter().map(|(_, s)| s.width).max()?;
+    let height = present.iter().map(|(_, s)| s.height).max()?;
+    let sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        address_mode_u: address_mode(first.wrap_s),
+        address_mode_v: address_mode(first.wrap_t),
+        ..ImageSamplerDescriptor::linear()
+    });
+    Some(((width, height), sampler))
+}
+
+/// Builds an RGBA8 image from linear texels. Rows are flipped because USD's
+/// `st` origin is the bottom left of the image.
+fn pack_image(
+    textures: &[Option<&LoadedTexture>],
+    srgb: bool,
+    texel: impl Fn(&dyn Fn(&TextureSamples) -> [f32; 4]) -> [f32; 4],
+) -> Option<Image> {
+    let ((width, height), sampler) = packed_layout(textures)?;
+    let mut data = Vec::with_capacity(width as usize * height as usize * 4);
+    for y in 0..height {
+        for x in 0..width {
+            let at = |samples: &TextureSamples| samples.at(x, height - 1 - y, width, height);
+            let value = texel(&at);
+            for (c, &channel) in value.iter().enumerate() {
+                let encoded = if srgb && c < 3 {
+                    Srgba::gamma_function_inverse(channel)
+                } else {
+                    channel
+                };
+                data.push((encoded.clamp(0.0, 1.0) * 255.0).round() as u8);
+            }
+        }
+    }
+
+    let format = if srgb {
+        TextureFormat::Rgba8UnormSrgb
+    } else {
+        TextureFormat::Rgba8Unorm
+    };
+    let mut image = Image::new(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        format,
+        RenderAssetUsages::default(),
+    );
+    image.sampler = sampler;
+    Some(image)
+}
+
+/// Bevy reads roughness from green and metallic from blue, while USD
+/// authors them as separate textures.
+fn metallic_roughness_image(
+    material: &MaterialData,
+    metallic: Option<&LoadedTexture>,
+    roughness: Option<&LoadedTexture>,
+) -> Option<Image> {
+    pack_image(&[metallic, roughness], false, |at| {
+        let metallic =
+            metallic.map_or(material.metallic, |(t, s)| channel_scalar(at(s), t.channel));
+        let roughness = roughness.map_or(material.roughness, |(t, s)| {
+            channel_scalar(at(s), t.channel)
+        });
+        [0.0, roughness, metallic, 1.0]
+    })
+}
+
+/// Loads a material's textures into `images` and points `standard` at them.
+///
+/// Textured inputs replace their constant, so the matching factors are reset
+/// to neutral values. Unreadable files use the texture's fallback and are
+/// returned as errors.
+pub fn apply_material_textures(
+    material: &MaterialData,
+    standard: &mut StandardMaterial,
+    images: &mut Assets<Image>,
+) -> Vec<TextureLoadError> {
+    let textures = &material.textures;
+    let mut errors = Vec::new();
+    let diffuse = read_texture(textures.diffuse_color.as_ref(), true, &mut errors);
+    let opacity = read_texture(textures.opacity.as_ref(), false, &mut errors);
+    let emissive = read_texture(textures.emissive_color.as_ref(), true, &mut errors);
+    let metallic = read_texture(textures.metallic.as_ref(), false, &mut errors);
+    let roughness = read_texture(textures.roughness.as_ref(), false, &mut errors);
+    let normal = read_texture(textures.normal.as_ref(), false, &mut errors);
+    let occlusion = read_texture(textures.occlusion.as_ref(), false, &mut errors);
+
+    let base_color = pack_image(&[diffuse.as_ref(), opacity.as_ref()], true, |at| {
+        let [r, g, b] = diffuse.as_ref().map_or(material.diffuse_color, |(t, s)| {
+            channel_rgb(at(s), t.channel)
+        });
+        let a = opacity
+            .as_ref()
+            .map_or(material.opacity, |(t, s)| channel_scalar(at(s), t.channel));
+        [r, g, b, a]
+    });
+    if let Some(image) = base_color {
+        standard.base_color = Color::WHITE;
+        standard.base_color_texture = Some(images.add(image));
+        if opacity.is_some() && standard.alpha_mode == AlphaMode::Opaque {
+            standard.alpha_mode = AlphaMode::Blend;
+        }
+    }
+
+    let emissive_image = pack_image(&[emissive.as_ref()], true, |at| {
+        let [r, g, b] = emissive.as_ref().map_or(material.emissive_color, |(t, s)| {
+            channel_rgb(at(s), t.channel)
+        });
+        [r, g, b, 1.0]
+    });
+    if let Some(image) = emissive_image {
+        standard.emissive = LinearRgba::WHITE;
+        standard.emissive_texture = Some(images.add(image));
+    }
+
+    if let Some(image) = metallic_roughness_image(material, metallic.as_ref(), roughness.as_ref()) {
+        standard.metallic = 1.0;
+        standard.perceptual_roughness = 1.0;
+        standard.metallic_roughness_texture = Some(images.add(image));
+    }
+
+    // scale and bias already mapped the normal into [-1, 1], Bevy wants it encoded
+    let normal_image = pack_image(&[normal.as_ref()], false, |at| {
+        let [x, y, z] = normal
+            .as_ref()
+            .map_or(material.normal, |(t, s)| channel_rgb(at(s), t.channel));
+        [x * 0.5 + 0.5, y * 0.5 + 0.5, z * 0.5 + 0.5, 1.0]
+    });
+    if let Some(image) = normal_image {
+        standard.normal_map_texture = Some(images.add(image));
+    }
+
+    let occlusion_image = pack_image(&[occlusion.as_ref()], false, |at| {
+        let value = occlusion
+            .as_ref()
+            .map_or(1.0, |(t, s)| channel_scalar(at(s), t.channel));
+        [value, value, value, 1.0]
+    });
+    if let Some(image) = occlusion_image {
+        standard.occlusion_texture = Some(images.add(image));
+    }
+
+    errors
+}
+
+/// Fallback look for a mesh without a bound material, driven by its
+/// `displayColor` and `displayOpacity`.
+pub fn display_material(mesh: &MeshData) -> StandardMaterial {
+    let constant_color = mesh
+        .primvars
+        .get("displayColor")
+        .and_then(PrimvarData::as_float3)
+        .map(|primvar| primvar.constant_value());
+    let constant_opacity = mesh
+        .primvars
+        .get("displayOpacity")
+        .and_then(PrimvarData::as_float)
+        .map(|primvar| primvar.constant_value());
+
+    // per-vertex colors multiply the base color, so it has to stay white
+    let base_color = match constant_color {
+        None => Color::srgb(0.7, 0.4, 1.0),
+        Some(Some([r, g, b])) => Color::linear_rgb(r, g, b),
+        Some(None) => Color::WHITE,
+    };
+    let opacity = constant_opacity.flatten().unwrap_or(1.0);
+    let varying_opacity = matches!(constant_opacity, Some(None));
+
+    let mut material = StandardMaterial::from(base_color.with_alpha(opacity));
+    if varying_opacity || opacity < 1.0 {
+        material.alpha_mode = AlphaMode::Blend;
+    }
+    material.double_sided = mesh.double_sided;
+
+    // ✅ Ensure culling is disabled when double-sided
+    if mesh.double_sided {
+        material.cull_mode = None;
+    }
+    material
+}
+
+// -------- Cameras --------
+/// Bevy projection matching the lens of a USD camera.
+///
+/// `units_to_meters` scales scene-unit distances; pass the stage's
+/// `metersPerUnit` when the scene was loaded with the root correction.
+/// Bevy overwrites the perspective aspect ratio with the viewport's, so
+/// keep [`fit_vertical_fov`] applied to show the whole film gate.
+pub fn camera_projection(camera: &CameraData, units_to_meters: f32) -> Projection {
+    let [near, far] = camera
+        .clipping_range
+        .map(|distance| distance * units_to_meters);
+    match camera.projection {
+        CameraProjection::Perspective => Projection::Perspective(PerspectiveProjection {
+            fov: camera.vertical_fov(),
+            aspect_ratio: camera.aspect_ratio(),
+            near,
+            far,
+        }),
+        CameraProjection::Orthographic => Projection::Orthographic(OrthographicProjection {
+            near,
+            far,
+            // apertures are in tenths of a scene unit, the gate stays whole
+            scaling_mode: ScalingMode::AutoMin {
+                min_width: camera.horizontal_aperture * 0.1 * units_to_meters,
+                min_height: camera.vertical_aperture * 0.1 * units_to_meters,
+            },
+            ..OrthographicProjection::default_3d()
+        }),
+    }
+}
+
+/// Vertical field of view that keeps the whole film gate of `camera` in a
+/// viewport `viewport_aspect` wide, widening it when the viewport is
+/// narrower than the gate.
+pub fn fit_vertical_fov(camera: &CameraData, viewport_aspect: f32) -> f32 {
+    let fov = camera.vertical_fov();
+    if viewport_aspect >= camera.aspect_ratio() {
+        return fov;
+    }
+    let half_width = (fov * 0.5).tan() * camera.aspect_ratio();
+    2.0 * (half_width / viewport_aspect).atan()
+}
+
+// -------- Lights --------
+/// A stage light converted by [`light_to_bevy`].
+#[derive(Debug, Clone)]
+pub enum BevyLight {
+    Directional(DirectionalLight),
+    Point(PointLight),
+    Spot(SpotLight),
+    /// Uniform environment radiance in linear RGB nits, see
+    /// [`uniform_environment_map`].
+    Environment([f32; 3]),
+}
+
+/// Exposure for cameras lit by stage lights. One nit reads as display white,
+/// as in USD's preview renderers, where Bevy's default suits daylight in lux.
+pub const STAGE_LIGHTS_EXPOSURE: Exposure = Exposure {
+    // log2(1 / 1.2), undoing the calibration constant of `Exposure::exposure`
+    ev100: -0.263,
+};
+
+/// Bevy light matching a USD light.
+///
+/// Intensities are nits. Distant lights shine them over a disk `angle` wide,
+/// so the default 50000 nit sun 0.53 degrees wide gives about 3.36 lux.
+/// Bevy has no area lights: spheres become point lights and one-sided disks
+/// and rects become spot lights over their hemisphere, all keeping the
+/// on-axis brightness of the emitter. `units_to_meters`
+/// converts light sizes like [`camera_projection`]. A dome texture that
+/// cannot be read is pushed to `errors` and the dome keeps its color.
+pub fn light_to_bevy(
+    light: &LightData,
+    units_to_meters: f32,
+    errors: &mut Vec<TextureLoadError>,
+) -> BevyLight {
+    let nits = light.scaled_intensity();
+    let [r, g, b] = light.linear_color();
+    let color = Color::linear_rgb(r, g, b);
+    // Bevy treats lumens as spread over a full sphere, 4pi times the candela
+    let lumens = |projected_area: f32| 4.0 * PI * nits * projected_area;
+    // without a cone the spot covers the emitter's hemisphere
+    let spot = |lumens: f32, radius: f32| {
+        let (outer_angle, softness) = light.cone.map_or((FRAC_PI_2, 0.0), |cone| {
+            (cone.angle.to_radians().min(FRAC_PI_2), cone.softness)
+        });
+        SpotLight {
+            color,
+            intensity: lumens,
+            radius,
+            shadows_enabled: true,
+            outer_angle,
+            inner_angle: outer_angle * (1.0 - softness),
+            ..default()
+        }
+    };
+
+    match light.kind {
+        LightKind::Distant { angle } => BevyLight::Directional(DirectionalLight {
+            color,
+            illuminance: distant_illuminance(nits, angle),
+            shadows_enabled: true,
+            ..default()
+        }),
+        LightKind::Sphere { radius } => {
+            let radius = radius * units_to_meters;
+            let intensity = lumens(PI * radius * radius);
+            if light.cone.is_some() {
+                return BevyLight::Spot(spot(intensity, radius));
+            }
+            BevyLight::Point(PointLight {
+                color,
+                intensity,
+                radius,
+                shadows_enabled: true,
+                ..default()
+            })
+        }
+        LightKind::Disk { radius } => {
+            let radius = radius * units_to_meters;
+            BevyLight::Spot(spot(lumens(PI * radius * radius), radius))
+        }
+        LightKind::Rect { width, height } => {
+            let (width, height) = (width * units_to_meters, height * units_to_meters);
+            let radius = 0.5 * width.hypot(height);
+            BevyLight::Spot(spot(lumens(width * height), radius))
+        }
+        LightKind::Dome { ref texture } => {
+            let mean = texture
+                .as_deref()
+                .and_then(|file| match mean_texel(file) {
+                    Ok(mean) => Some(mean),
+                    Err(err) => {
+                        errors.push(err);
+                        None
+                    }
+                })
+                .unwrap_or([1.0; 3]);
+            BevyLight::Environment([r * mean[0] * nits, g * mean[1] * nits, b * mean[2] * nits])
+        }
+    }
+}
+
+/// Lux from a distant light of `nits` over a disk `angle` degrees wide, or
+/// `nits` itself when the light has no angular size.
+fn distant_illuminance(nits: f32, angle: f32) -> f32 {
+    if angle <= 0.0 {
+        return nits;
+    }
+    // solid angle of the disk, 2pi(1 - cos(angle / 2)) without the cancellation
+    let quarter = (0.25 * angle.min(360.0)).to_radians();
+    nits * 4.0 * PI * quarter.sin().powi(2)
+}
+
+/// Average color of an environment texture; values above 1 are clipped.
+fn mean_texel(file: &str) -> Result<[f32; 3], TextureLoadError> {
+    let texture = TextureData {
+        file: file.to_string(),
+        channel: TextureChannel::Rgb,
+        wrap_s: TextureWrap::Repeat,
+        wrap_t: TextureWrap::Clamp,
+        color_space: SourceColorSpace::Auto,
+        scale: [1.0; 4],
+        bias: [0.0; 4],
+        fallback: [1.0; 4],
+    };
+    let samples = decode_texture(&texture, true)?;
+    let count = samples.texels.len().max(1) as f32;
+    let mut sum = [0.0; 3];
+    for texel in &samples.texels {
+        for (total, value) in sum.iter_mut().zip(texel) {
+            *total += value;
+        }
+    }
+    Ok(sum.map(|total| total / count))
+}
+
+/// An environment map light shining `radiance` evenly from every direction.
+pub fn uniform_environment_map(
+    radiance: [f32; 3],
+    images: &mut Assets<Image>,
+) -> EnvironmentMapLight {
+    // the brightest channel goes into the intensity so 8 bits are enough
+    let peak = radiance.into_iter().fold(0.0, f32::max);
+    let texel = radiance.map(|c| if peak > 0.0 { c / peak } else { 0.0 });
+    let pixel = [texel[0], texel[1], texel[2], 1.0].map(|c| (c * 255.0).round() as u8);
+    let mut image = Image::new(
+        Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 6,
+        },
+        TextureDimension::D2,
+        pixel.repeat(6),
+        TextureFormat::Rgba8Unorm,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::Cube),
+        ..default()
+    });
+    let handle = images.add(image);
+    EnvironmentMapLight {
+        diffuse_map: handle.clone(),
+        specular_map: handle,
+        intensity: peak,
+        ..default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_rs_loader::SubdivisionData;
+
+    /// Two quads sharing an edge.
+    fn two_quads() -> MeshData {
+        MeshData {
+            positions: vec![
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [2.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0],
+                [1.0, 1.0, 0.0],
+                [2.0, 1.0, 0.0],
+            ],
+            face_vertex_counts: vec![4, 4],
+            face_vertex_indices: vec![0, 1, 4, 3, 1, 2, 5, 4],
+            primvars: HashMap::new(),
+            double_sided: false,
+            material_index: None,
+            subdivision: SubdivisionData::default(),
+            hole_indices: Vec::new(),
+            orientation: Orientation::RightHanded,
+        }
+    }
+
+    fn primvar<T>(
+        values: Vec<T>,
+        indices: Option<Vec<usize>>,
+        interpolation: PrimvarInterpolation,
+    ) -> Primvar<T> {
+        Primvar {
+            values,
+            indices,
+            interpolation,
+            element_size: 1,
+            type_name: String::new(),
+        }
+    }
+
+    fn skip() -> MeshConversionOptions {
+        MeshConversionOptions {
+            bad_faces: BadFacePolicy::Skip,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn converts_valid_mesh() {
+        let mesh = try_meshdata_to_bevy(&two_quads(), &MeshConversionOptions::default()).unwrap();
+        assert_eq!(mesh.indices().unwrap().len(), 12);
+    }
+
+    #[test]
+    fn refines_subdivision_surfaces() {
+        let options = MeshConversionOptions {
+            subdivision_level: 1,
+            ..Default::default()
+        };
+        let mesh = try_meshdata_to_bevy(&two_quads(), &options).unwrap();
+        assert_eq!(mesh.indices().unwrap().len(), 12);
+
+        let mut data = two_quads();
+        data.subdivision.scheme = SubdivisionScheme::CatmullClark;
+        let mesh = try_meshdata_to_bevy(&data, &options).unwrap();
+        assert_eq!(mesh.indices().unwrap().len(), 8 * 6);
+    }
+
+    #[test]
+    fn hole_faces_are_not_drawn() {
+        let mut data = two_quads();
+        data.hole_indices = vec![1];
+        let mesh = try_meshdata_to_bevy(&data, &MeshConversionOptions::default()).unwrap();
+        assert_eq!(mesh.indices().unwrap().len(), 6);
+
+        // the children of a subdivided hole are dropped too
+        data.subdivision.scheme = SubdivisionScheme::CatmullClark;
+        let options = MeshConversionOptions {
+            subdivision_level: 1,
+            ..Default::default()
+        };
+        let mesh = try_meshdata_to_bevy(&data, &options).unwrap();
+        assert_eq!(mesh.indices().unwrap().len(), 4 * 6);
+    }
+
+    /// Triangulate a single face and return the area of every triangle
+    /// measured along `normal`.
+    fn triangle_areas(points: &[[f32; 3]], normal: Vec3) -> Vec<f32> {
+        let positions: Vec<Vec3> = points.iter().map(|&p| Vec3::from(p)).collect();
+        let face: Vec<usize> = (0..points.len()).collect();
+        let corners = triangulate(&positions, &[face.len()], &face, Orientation::RightHanded);
+        corners
+            .chunks(3)
+            .map(|t| {
+                let [a, b, c] = [t[0], t[1], t[2]].map(|w| positions[w]);
+                0.5 * (b - a).cross(c - a).dot(normal)
+            })
+            .collect()
+    }
+
+    fn assert_tiles(areas: &[f32], sides: usize, area: f32) {
+        assert_eq!(areas.len(), sides - 2);
+        assert!(
+            areas.iter().all(|&a| a > 0.0),
+            "folded triangle in {areas:?}"
+        );
+        assert!((areas.iter().sum::<f32>() - area).abs() < 1e-4);
+    }
+
+    #[test]
+    fn concave_faces_are_ear_clipped() {
+        // L: a 2x2 square missing its top right quarter
+        let l_shape = [
+            [0.0, 0.0, 0.0],
+            [2.0, 0.0, 0.0],
+            [2.0, 1.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [1.0, 2.0, 0.0],
+            [0.0, 2.0, 0.0],
+        ];
+        assert_tiles(&triangle_areas(&l_shape, Vec3::Z), 6, 3.0);
+
+        // U: a 3x2 block with a 1x1 notch cut from the top middle
+        let u_shape = [
+            [0.0, 0.0, 0.0],
+            [3.0, 0.0, 0.0],
+            [3.0, 2.0, 0.0],
+            [2.0, 2.0, 0.0],
+            [2.0, 1.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [1.0, 2.0, 0.0],
+            [0.0, 2.0, 0.0],
+        ];
+        assert_tiles(&triangle_areas(&u_shape, Vec3::Z), 8, 5.0);
+
+        // an arrowhead quad whose fan diagonal would run outside of it
+        let arrowhead = [
+            [0.0, 0.0, 0.0],
+            [1.0, 3.0, 0.0],
+            [2.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+        ];
+        assert_tiles(&triangle_areas(&arrowhead, Vec3::NEG_Z), 4, 2.0);
+    }
+
+    #[test]
+    fn non_planar_faces_use_their_best_fit_plane() {
+        // the U shape tilted about X, with its corners lifted off the plane
+        let rotation = Quat::from_rotation_x(0.7);
+        let u_shape: Vec<[f32; 3]> = [
+            [0.0, 0.0, 0.02],
+            [3.0, 0.0, -0.02],
+            [3.0, 2.0, 0.02],
+            [2.0, 2.0, -0.02],
+            [2.0, 1.0, 0.02],
+            [1.0, 1.0, -0.02],
+            [1.0, 2.0, 0.02],
+            [0.0, 2.0, -0.02],
+        ]
+        .iter()
+        .map(|&p| (rotation * Vec3::from(p)).to_array())
+        .collect();
+        let areas = triangle_areas(&u_shape, rotation * Vec3::Z);
+        assert_eq!(areas.len(), 6);
+        assert!(
+            areas.iter().all(|&a| a > 0.0),
+            "folded triangle in {areas:?}"
+        );
+        assert!((areas.iter().sum::<f32>() - 5.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn large_concave_faces_are_ear_clipped() {
+        // a star with 500 spikes, every other corner reflex
+        let spikes = 500;
+        let star: Vec<[f32; 3]> = (0..2 * spikes)
+            .map(|i| {
+                let radius = if i % 2 == 0 { 1.0 } else { 0.5 };
+                let angle = i as f32 * std::f32::consts::PI / spikes as f32;
+                [radius * angle.cos(), radius * angle.sin(), 0.0]
+            })
+            .collect();
+        let area = 0.5 * spikes as f32 * (std::f32::consts::PI / spikes as f32).sin();
+        assert_tiles(&triangle_areas(&star, Vec3::Z), 2 * spikes, area);
+    }
+
+    #[test]
+    fn winding_follows_orientation() {
+        // the first triangle's geometric normal and the generated normal
+        let facing = |orientation| {
+            let mut data = two_quads();
+            data.orientation = orientation;
+            let mesh = try_meshdata_to_bevy(&data, &MeshConversionOptions::default()).unwrap();
+            let positions = mesh
+                .attribute(Mesh::ATTRIBUTE_POSITION)
+                .and_then(VertexAttributeValues::as_float3)
+                .unwrap();
+            let normals = mesh
+                .attribute(Mesh::ATTRIBUTE_NORMAL)
+                .and_then(VertexAttributeValues::as_float3)
+                .unwrap();
+            let corner =
+                |i: usize| Vec3::from(positions[mesh.indices().unwrap().iter().nth(i).unwrap()]);
+            let winding = (corner(1) - corner(0)).cross(corner(2) - corner(0));
+            (winding.normalize(), Vec3::from(normals[0]))
+        };
+
+        assert_eq!(facing(Orientation::RightHanded), (Vec3::Z, Vec3::Z));
+        assert_eq!(facing(Orientation::LeftHanded), (Vec3::NEG_Z, Vec3::NEG_Z));
+    }
+
+    #[test]
+    fn shared_wedges_are_welded() {
+        let mesh = meshdata_to_bevy(&two_quads());
+        assert_eq!(mesh.count_vertices(), 6);
+        assert!(matches!(mesh.indices(), Some(Indices::U16(indices)) if indices.len() == 12));
+
+        // a uv seam along the shared edge keeps its vertices apart
+        let mut data = two_quads();
+        data.primvars.insert(
+            "st".to_string(),
+            PrimvarData::Float2(primvar(
+                vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]],
+                Some(vec![0, 1, 2, 3, 0, 1, 2, 3]),
+                PrimvarInterpolation::FaceVarying,
+            )),
+        );
+        assert_eq!(meshdata_to_bevy(&data).count_vertices(), 8);
+    }
+
+    #[test]
+    fn large_meshes_use_32_bit_indices() {
+        // separate unit squares side by side, nothing can be welded
+        let squares = |count: usize| MeshData {
+            positions: (0..count * 4)
+                .map(|i| {
+                    let [y, z] = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]][i % 4];
+                    [(i / 4) as f32, y, z]
+                })
+                .collect(),
+            face_vertex_counts: vec![4; count],
+            face_vertex_indices: (0..count * 4).collect(),
+            ..two_quads()
+        };
+
+        let mesh = meshdata_to_bevy(&squares(1 << 14));
+        assert_eq!(mesh.count_vertices(), 1 << 16);
+        assert!(matches!(mesh.indices(), Some(Indices::U16(_))));
+
+        let mesh = meshdata_to_bevy(&squares((1 << 14) + 1));
+        assert!(matches!(mesh.indices(), Some(Indices::U32(_))));
+    }
+
+    /// Unit cube whose faces share their corners.
+    fn shared_cube() -> MeshData {
+        MeshData {
+            positions: (0..8)
+                .map(|i| [i & 1, (i >> 1) & 1, (i >> 2) & 1].map(|bit| bit as f32))
+                .collect(),
+            face_vertex_counts: vec![4; 6],
+            face_vertex_indices: vec![
+                0, 2, 3, 1, 4, 5, 7, 6, 0, 1, 5, 4, 2, 6, 7, 3, 0, 4, 6, 2, 1, 3, 7, 5,
+            ],
+            ..two_quads()
+        }
+    }
+
+    fn normals_of(mesh: &Mesh) -> &[[f32; 3]] {
+        mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+            .and_then(VertexAttributeValues::as_float3)
+            .unwrap()
+    }
+
+    #[test]
+    fn smooth_normals_split_at_the_crease_angle() {
+        // the cube's faces meet at 90 degrees, beyond the default crease angle
+        let mesh = meshdata_to_bevy(&shared_cube());
+        assert_eq!(mesh.count_vertices(), 24);
+        for normal in normals_of(&mesh) {
+            let largest = normal.iter().fold(0.0_f32, |m, c| m.max(c.abs()));
+            assert!((largest - 1.0).abs() < 1e-6, "{normal:?}");
+        }
+
+        let options = MeshConversionOptions {
+            normals: NormalGeneration::Smooth {
+                crease_angle: 100_f32.to_radians(),
+            },
+            ..Default::default()
+        };
+        let mesh = try_meshdata_to_bevy(&shared_cube(), &options).unwrap();
+        assert_eq!(mesh.count_vertices(), 8);
+        let positions = mesh
+            .attribute(Mesh::ATTRIBUTE_POSITION)
+            .and_then(VertexAttributeValues::as_float3)
+            .unwrap();
+        for (position, normal) in positions.iter().zip(normals_of(&mesh)) {
+            let outward = (Vec3::from(*position) - Vec3::splat(0.5)).normalize();
+            assert!(Vec3::from(*normal).abs_diff_eq(outward, 1e-6), "{normal:?}");
+        }
+    }
+
+    #[test]
+    fn creases_and_flat_shading_keep_edges_hard() {
+        // the second quad is folded up by 30 degrees along the shared edge
+        let mut folded = two_quads();
+        let (sin, cos) = 30_f32.to_radians().sin_cos();
+        for point in [2, 5] {
+            folded.positions[point] = [1.0 + cos, folded.positions[point][1], sin];
+        }
+        assert_eq!(meshdata_to_bevy(&folded).count_vertices(), 6);
+
+        let flat = MeshConversionOptions {
+            normals: NormalGeneration::Flat,
+            ..Default::default()
+        };
+        let mesh = try_meshdata_to_bevy(&folded, &flat).unwrap();
+        assert_eq!(mesh.count_vertices(), 8);
+
+        folded.subdivision.crease_edges = vec![([4, 1], 10.0)];
+        assert_eq!(meshdata_to_bevy(&folded).count_vertices(), 8);
+    }
+
+    fn tangents_of(mesh: &Mesh) -> &[[f32; 4]] {
+        match mesh.attribute(Mesh::ATTRIBUTE_TANGENT) {
+            Some(VertexAttributeValues::Float32x4(tangents)) => tangents,
+            _ => panic!("expected tangents"),
+        }
+    }
+
+    #[test]
+    fn textured_cube_gets_mikktspace_tangents() {
+        // every side maps s along its first edge direction and t along its second
+        let mut cube = crate::gprims::cube(2.0);
+        let mut st = primvar(
+            [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]].repeat(6),
+            None,
+            PrimvarInterpolation::Vertex,
+        );
+        st.type_name = "texCoord2f[]".to_string();
+        cube.primvars
+            .insert("st".to_string(), PrimvarData::Float2(st));
+
+        let plain = meshdata_to_bevy(&cube);
+        assert!(plain.attribute(Mesh::ATTRIBUTE_TANGENT).is_none());
+
+        let options = MeshConversionOptions {
+            generate_tangents: true,
+            ..Default::default()
+        };
+        let mesh = try_meshdata_to_bevy(&cube, &options).unwrap();
+        let normals = normals_of(&mesh);
+        let tangents = tangents_of(&mesh);
+        assert_eq!(tangents.len(), 24);
+        for (normal, tangent) in normals.iter().zip(tangents) {
+            let expected = match normal.map(|c| c.round() as i32) {
+                [1, 0, 0] | [0, 0, -1] => [0.0, 1.0, 0.0],
+                [-1, 0, 0] | [0, 1, 0] => [0.0, 0.0, 1.0],
+                _ => [1.0, 0.0, 0.0],
+            };
+            let [x, y, z, w] = *tangent;
+            assert!(
+                Vec3::new(x, y, z).abs_diff_eq(Vec3::from(expected), 1e-5),
+                "{normal:?} {tangent:?}"
+            );
+            assert_eq!(w, 1.0);
+        }
+    }
+
+    #[test]
+    fn authored_tangents_are_used() {
+        let mut data = two_quads();
+        data.primvars.insert(
+            "st".to_string(),
+            PrimvarData::Float2(primvar(
+                vec![[0.0, 0.0]; 6],
+                None,
+                PrimvarInterpolation::Vertex,
+            )),
+        );
+        data.primvars.insert(
+            "tangents".to_string(),
+            PrimvarData::Float3(primvar(
+                vec![[0.0, 1.0, 0.0]],
+                None,
+                PrimvarInterpolation::Constant,
+            )),
+        );
+        let options = MeshConversionOptions {
+            generate_tangents: true,
+            ..Default::default()
+        };
+        let mesh = try_meshdata_to_bevy(&data, &options).unwrap();
+        assert!(tangents_of(&mesh)
+            .iter()
+            .all(|&tangent| tangent == [0.0, 1.0, 0.0, 1.0]));
+        let custom = primvar_vertex_attribute("tangents", 0, VertexFormat::Float32x3);
+        assert!(mesh.attribute(custom).is_none());
+    }
+
+    #[test]
+    fn unusable_tangents_stay_custom_primvars() {
+        let mut data = two_quads();
+        data.primvars.insert(
+            "tangents".to_string(),
+            PrimvarData::Int(primvar(vec![1; 6], None, PrimvarInterpolation::Vertex)),
+        );
+        let options = MeshConversionOptions {
+            primvar_attributes: vec!["tangents"],
+            ..Default::default()
+        };
+        let mesh = try_meshdata_to_bevy(&data, &options).unwrap();
+        assert!(mesh.attribute(Mesh::ATTRIBUTE_TANGENT).is_none());
+        let custom = primvar_vertex_attribute("tangents", 0, VertexFormat::Sint32);
+        assert!(mesh.attribute(custom).is_some());
+    }
+
+    #[test]
+    fn rejects_out_of_range_index() {
+        let mut data = two_quads();
+        data.face_vertex_indices[6] = 42;
+        let err = try_meshdata_to_bevy(&data, &MeshConversionOptions::default()).unwrap_err();
+        assert_eq!(
+            err,
+            MeshConversionError::IndexOutOfRange {
+                face: 1,
+                wedge: 6,
+                index: 42,
+                vertex_count: 6,
+            }
+        );
+    }
+
+    #[test]
+    fn skips_out_of_range_face() {
+        let mut data = two_quads();
+        data.face_vertex_indices[6] = 42;
+        data.primvars.insert(
+            "st".to_string(),
+            PrimvarData::Float2(primvar(
+                vec![[0.5, 0.5]; 8],
+                None,
+                PrimvarInterpolation::FaceVarying,
+            )),
+        );
+        let mesh = try_meshdata_to_bevy(&data, &skip()).unwrap();
+        assert_eq!(mesh.indices().unwrap().len(), 6);
+    }
+
+    #[test]
+    fn rejects_count_mismatch() {
+        let mut data = two_quads();
+        data.face_vertex_counts = vec![4, 4, 3];
+        let err = try_meshdata_to_bevy(&data, &MeshConversionOptions::default()).unwrap_err();
+        assert_eq!(
+            err,
+            MeshConversionError::FaceCountMismatch {
+                counted: 11,
+                indices: 8,
+            }
+        );
+        let mesh = try_meshdata_to_bevy(&data, &skip()).unwrap();
+        assert_eq!(mesh.indices().unwrap().len(), 12);
+    }
+
+    #[test]
+    fn all_faces_bad_is_an_error() {
+        let mut data = two_quads();
+        data.positions.truncate(1);
+        let err = try_meshdata_to_bevy(&data, &skip()).unwrap_err();
+        assert_eq!(err, MeshConversionError::NoValidFaces { skipped: 2 });
+    }
+
+    #[test]
+    fn uniform_display_color_becomes_vertex_colors() {
+        let mut data = two_quads();
+        data.primvars.insert(
+            "displayColor".to_string(),
+            PrimvarData::Float3(primvar(
+                vec![[1.0, 0.0, 0.0], [0.0, 0.0, 1.0]],
+                Some(vec![1, 0]),
+                PrimvarInterpolation::Uniform,
+            )),
+        );
+        let mesh = meshdata_to_bevy(&data);
+        let Some(VertexAttributeValues::Float32x4(colors)) = mesh.attribute(Mesh::ATTRIBUTE_COLOR)
+        else {
+            panic!("expected vertex colors");
+        };
+        // the shared edge is split where the colors differ
+        assert_eq!(colors.len(), 8);
+        assert_eq!(colors[0], [0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(colors[7], [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(display_material(&data).base_color, Color::WHITE);
+    }
+
+    #[test]
+    fn bound_meshes_keep_their_vertex_colors() {
+        // the binding may differ per instance, the viewer drops the colors
+        let mut data = two_quads();
+        data.material_index = Some(0);
+        data.primvars.insert(
+            "displayColor".to_string(),
+            PrimvarData::Float3(primvar(
+                vec![[1.0, 0.0, 0.0], [0.0, 0.0, 1.0]],
+                None,
+                PrimvarInterpolation::Uniform,
+            )),
+        );
+        let mesh = meshdata_to_bevy(&data);
+        assert!(mesh.attribute(Mesh::ATTRIBUTE_COLOR).is_some());
+    }
+
+    #[test]
+    fn constant_display_color_tints_material() {
+        let mut data = two_quads();
+        data.primvars.insert(
+            "displayColor".to_string(),
+            PrimvarData::Float3(primvar(
+                vec![[0.2, 0.4, 0.6]],
+                None,
+                PrimvarInterpolation::Constant,
+            )),
+        );
+        data.primvars.insert(
+            "displayOpacity".to_string(),
+            PrimvarData::Float(primvar(vec![0.5], None, PrimvarInterpolation::Constant)),
+        );
+        let mesh = meshdata_to_bevy(&data);
+        assert!(mesh.attribute(Mesh::ATTRIBUTE_COLOR).is_none());
+
+        let material = display_material(&data);
+        assert_eq!(material.base_color, Color::linear_rgba(0.2, 0.4, 0.6, 0.5));
+        assert_eq!(material.alpha_mode, AlphaMode::Blend);
+    }
+
+    #[test]
+    fn skipped_faces_drop_their_colors() {
+        let mut data = two_quads();
+        data.face_vertex_indices[5] = 42;
+        data.primvars.insert(
+            "displayColor".to_string(),
+            PrimvarData::Float3(primvar(
+                vec![[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+                None,
+                PrimvarInterpolation::Uniform,
+            )),
+        );
+        let mesh = try_meshdata_to_bevy(&data, &skip()).unwrap();
+        let Some(VertexAttributeValues::Float32x4(colors)) = mesh.attribute(Mesh::ATTRIBUTE_COLOR)
+        else {
+            panic!("expected vertex colors");
+        };
+        assert!(colors.iter().all(|&c| c == [1.0, 0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn indexed_uvs_follow_interpolation() {
+        let mut data = two_quads();
+        // face-varying st with indices, one quad mapped to each corner of the unit square
+        data.primvars.insert(
+            "st".to_string(),
+            PrimvarData::Float2(primvar(
+                vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]],
+                Some(vec![0, 1, 2, 3, 0, 1, 2, 3]),
+                PrimvarInterpolation::FaceVarying,
+            )),
+        );
+        let mut lightmap = primvar(vec![[0.25, 0.5]; 6], None, PrimvarInterpolation::Vertex);
+        lightmap.type_name = "texCoord2f[]".to_string();
+        data.primvars
+            .insert("lightmap".to_string(), PrimvarData::Float2(lightmap));
+
+        let mesh = meshdata_to_bevy(&data);
+        let Some(VertexAttributeValues::Float32x2(uvs)) = mesh.attribute(Mesh::ATTRIBUTE_UV_0)
+        else {
+            panic!("expected uvs");
+        };
+        // first fan triangle of the first quad is wedges 0, 1, 2
+        assert_eq!(&uvs[..3], &[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0]]);
+        let Some(VertexAttributeValues::Float32x2(uvs)) = mesh.attribute(Mesh::ATTRIBUTE_UV_1)
+        else {
+            panic!("expected a second uv set");
+        };
+        assert!(uvs.iter().all(|&uv| uv == [0.25, 0.5]));
+    }
+
+    #[test]
+    fn requested_primvars_become_vertex_attributes() {
+        let mut data = two_quads();
+        let weights = primvar(
+            vec![0.0, 0.0, 0.0, 0.0, 0.5, 0.5],
+            None,
+            PrimvarInterpolation::Vertex,
+        );
+        data.primvars
+            .insert("weight".to_string(), PrimvarData::Float(weights));
+        data.primvars.insert(
+            "userProperties".to_string(),
+            PrimvarData::Float(primvar(vec![1.0], None, PrimvarInterpolation::Constant)),
+        );
+        let options = MeshConversionOptions {
+            primvar_attributes: vec!["userProperties", "weight"],
+            ..Default::default()
+        };
+        let attribute = primvar_vertex_attribute("weight", 1, VertexFormat::Float32);
+        assert!(meshdata_to_bevy(&data).attribute(attribute).is_none());
+
+        let mesh = try_meshdata_to_bevy(&data, &options).unwrap();
+        // constant primvars stay off the vertices even when asked for
+        let constant = primvar_vertex_attribute("userProperties", 0, VertexFormat::Float32);
+        assert!(mesh.attribute(constant).is_none());
+        // shaders can rebuild the attribute from the base id alone
+        let rebuilt = MeshVertexAttribute::new(
+            "weight",
+            PRIMVAR_ATTRIBUTE_ID_BASE + 1,
+            VertexFormat::Float32,
+        );
+        assert_eq!(rebuilt.id, attribute.id);
+        let Some(VertexAttributeValues::Float32(weights)) = mesh.attribute(rebuilt) else {
+            panic!("expected the custom attribute");
+        };
+        assert_eq!(&weights[..3], &[0.0, 0.0, 0.5]);
+    }
+
+    fn texture(channel: TextureChannel) -> TextureData {
+        TextureData {
+            file: "/missing/texture.png".to_string(),
+            channel,
+            wrap_s: TextureWrap::Repeat,
+            wrap_t: TextureWrap::Repeat,
+            color_space: SourceColorSpace::Auto,
+            scale: [1.0; 4],
+            bias: [0.0; 4],
+            fallback: [0.25, 0.5, 0.75, 1.0],
+        }
+    }
+
+    #[test]
+    fn packs_metallic_and_roughness() {
+        let metallic = texture(TextureChannel::R);
+        // one column, bottom row 0.0 and top row 1.0
+        let samples = TextureSamples {
+            width: 1,
+            height: 2,
+            texels: vec![[1.0, 0.0, 0.0, 1.0], [0.0, 0.0, 0.0, 1.0]],
+        };
+        let material = MaterialData {
+            roughness: 0.2,
+            ..MaterialData::default()
+        };
+        let image = metallic_roughness_image(&material, Some(&(&metallic, samples)), None).unwrap();
+        assert_eq!((image.width(), image.height()), (1, 2));
+        // rows flip so the bottom of the file lands at v = 1
+        assert_eq!(image.data.unwrap(), vec![0, 51, 0, 255, 0, 51, 255, 255]);
+    }
+
+    #[test]
+    fn missing_texture_uses_fallback() {
+        let material = MaterialData {
+            textures: crate::open_rs_loader::MaterialTextures {
+                roughness: Some(texture(TextureChannel::G)),
+                ..Default::default()
+            },
+            ..MaterialData::default()
+        };
+        let mut standard = materialdata_to_bevy(&material, false);
+        let mut images = Assets::<Image>::default();
+        let errors = apply_material_textures(&material, &mut standard, &mut images);
+
+        assert!(matches!(errors.as_slice(), [TextureLoadError::Read { .. }]));
+        let handle = standard.metallic_roughness_texture.unwrap();
+        let data = images.get(&handle).unwrap().data.clone().unwrap();
+        assert_eq!(data, vec![0, 128, 0, 255]);
+        assert_eq!(standard.perceptual_roughness, 1.0);
+    }
+
+    fn light(kind: LightKind) -> LightData {
+        LightData {
+            path: "/light".to_string(),
+            kind,
+            intensity: 1000.0,
+            exposure: 0.0,
+            color: [1.0, 0.5, 0.25],
+            color_temperature: None,
+            cone: None,
+            transform: crate::open_rs_loader::InstanceTransform::Static([[0.0; 4]; 4]),
+        }
+    }
+
+    #[test]
+    fn sphere_light_keeps_its_on_axis_brightness() {
+        let BevyLight::Point(point) = light_to_bevy(
+            &light(LightKind::Sphere { radius: 10.0 }),
+            0.01,
+            &mut Vec::new(),
+        ) else {
+            panic!("expected a point light");
+        };
+        assert!((point.radius - 0.1).abs() < 1e-6);
+        // 4pi * 1000 nits * pi * 0.1^2
+        assert!(
+            (point.intensity - 394.784).abs() < 0.01,
+            "{}",
+            point.intensity
+        );
+        assert_eq!(point.color, Color::linear_rgb(1.0, 0.5, 0.25));
+    }
+
+    #[test]
+    fn default_distant_light_is_dim_daylight() {
+        let mut sun = light(LightKind::Distant { angle: 0.53 });
+        sun.intensity = 50_000.0;
+        let BevyLight::Directional(directional) = light_to_bevy(&sun, 1.0, &mut Vec::new()) else {
+            panic!("expected a directional light");
+        };
+        assert!(
+            (directional.illuminance - 3.36).abs() < 0.01,
+            "{}",
+            directional.illuminance
+        );
+        // a white diffuse surface facing it reads close to display white
+        let luminance = directional.illuminance / PI;
+        assert!((luminance * STAGE_LIGHTS_EXPOSURE.exposure() - 1.07).abs() < 0.01);
+
+        let BevyLight::Directional(point_like) = light_to_bevy(
+            &light(LightKind::Distant { angle: 0.0 }),
+            1.0,
+            &mut Vec::new(),
+        ) else {
+            panic!("expected a directional light");
+        };
+        assert_eq!(point_like.illuminance, 1000.0);
+    }
+
+    #[test]
+    fn shaped_and_area_lights_become_spots() {
+        let mut shaped = light(LightKind::Sphere { radius: 0.5 });
+        shaped.cone = Some(crate::open_rs_loader::LightCone {
+            angle: 30.0,
+            softness: 0.5,
+        });
+        let BevyLight::Spot(spot) = light_to_bevy(&shaped, 1.0, &mut Vec::new()) else {
+            panic!("expected a spot light");
+        };
+        assert!((spot.outer_angle - 30f32.to_radians()).abs() < 1e-6);
+        assert!((spot.inner_angle - 15f32.to_radians()).abs() < 1e-6);
+
+        let rect = LightKind::Rect {
+            width: 2.0,
+            height: 1.0,
+        };
+        let BevyLight::Spot(spot) = light_to_bevy(&light(rect), 1.0, &mut Vec::new()) else {
+            panic!("expected a spot light");
+        };
+        assert_eq!(spot.outer_angle, FRAC_PI_2);
+        assert!((spot.intensity - 4.0 * PI * 2000.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn dome_light_without_texture_is_uniform() {
+        let mut errors = Vec::new();
+        let dome = light(LightKind::Dome { texture: None });
+        let BevyLight::Environment(radiance) = light_to_bevy(&dome, 0.01, &mut errors) else {
+            panic!("expected an environment light");
+        };
+        assert_eq!(radiance, [1000.0, 500.0, 250.0]);
+        assert!(errors.is_empty());
+
+        let mut images = Assets::<Image>::default();
+        let environment = uniform_environment_map(radiance, &mut images);
+        assert_eq!(environment.intensity, 1000.0);
+        let image = images.get(&environment.diffuse_map).unwrap();
+        assert_eq!(&image.data.as_ref().unwrap()[..4], &[255, 128, 64, 255]);
+    }
+
+    fn camera(projection: CameraProjection) -> CameraData {
+        CameraData {
+            path: "/cam".to_string(),
+            projection,
+            focal_length: 50.0,
+            horizontal_aperture: 36.0,
+            vertical_aperture: 24.0,
+            clipping_range: [10.0, 10_000.0],
+            focus_distance: 0.0,
+            transform: crate::open_rs_loader::InstanceTransform::Static([[0.0; 4]; 4]),
+        }
+    }
+
+    #[test]
+    fn perspective_camera_matches_lens() {
+        let Projection::Perspective(projection) =
+            camera_projection(&camera(CameraProjection::Perspective), 0.01)
+        else {
+            panic!("expected a perspective projection");
+        };
+        // a 50mm lens on a full frame back is about 27 degrees vertically
+        assert!((projection.fov.to_degrees() - 26.99).abs() < 0.01);
+        assert_eq!(projection.aspect_ratio, 1.5);
+        assert!((projection.near - 0.1).abs() < 1e-6);
+        assert!((projection.far - 100.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn orthographic_camera_uses_aperture_as_extent() {
+        let Projection::Orthographic(projection) =
+            camera_projection(&camera(CameraProjection::Orthographic), 1.0)
+        else {
+            panic!("expected an orthographic projection");
+        };
+        assert!(matches!(
+            projection.scaling_mode,
+            ScalingMode::AutoMin { min_width, min_height }
+                if (min_width - 3.6).abs() < 1e-6 && (min_height - 2.4).abs() < 1e-6
+        ));
+    }
+
+    #[test]
+    fn narrow_viewports_widen_the_vertical_fov() {
+        let lens = camera(CameraProjection::Perspective);
+        assert_eq!(fit_vertical_fov(&lens, 2.0), lens.vertical_fov());
+        assert_eq!(fit_vertical_fov(&lens, 1.5), lens.vertical_fov());
+
+        // a square viewport keeps the horizontal view of the 3:2 gate
+        let fov = fit_vertical_fov(&lens, 1.0);
+        let horizontal = 2.0 * (36.0_f32 / 100.0).atan();
+        assert!((fov - horizontal).abs() < 1e-5);
+    }
 }